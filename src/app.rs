@@ -1,70 +1,663 @@
-use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use crate::browser_keys;
+use crate::deep_link;
+use crate::editor::Editors;
+use crate::error_log::ErrorLog;
+use crate::file_io;
+use crate::find;
+use crate::markdown;
+use crate::mock;
+use crate::notifications::Notifications;
+use crate::palette::{Action, CommandPalette};
+use crate::request_id;
+use crate::search::{self, SearchIndex};
+use crate::settings::{HiddenPolling, SettingsComponent};
+use egui_commonmark::CommonMarkCache;
 use ehttp::Request;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use std::sync::mpsc;
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+use web_time::Instant;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Entry {
     pub id: String,
     pub text: String,
+    /// Left out when empty, for servers that don't know about tags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Only sent by servers that track it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<Timestamp>,
+    /// Whatever else the server sent, kept so the inspector shows it and saving an edit
+    /// sends it back unchanged.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-enum EditorState {
-    View,
-    Edit
+/// A point in time as the server sends it: seconds since the epoch, or an RFC 3339 string.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Timestamp {
+    Epoch(i64),
+    Text(String),
 }
 
-#[derive(serde::Deserialize, serde::Serialize)]
-#[serde(default)] // if we add new fields, give them default values when deserializing old state
-struct EditorComponent {
-    entry: Option<Entry>,
-    state: EditorState
+impl Timestamp {
+    /// `None` if the server sent something that isn't a time.
+    fn parse(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self {
+            Self::Epoch(seconds) => chrono::DateTime::from_timestamp(*seconds, 0),
+            Self::Text(text) => chrono::DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|time| time.to_utc()),
+        }
+    }
 }
 
-impl EditorComponent {
+impl Entry {
+    fn created(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.created_at.as_ref().and_then(Timestamp::parse)
+    }
+}
+
+/// How long ago `time` was, e.g. "3h ago".
+fn relative_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    let elapsed = chrono::Utc::now().signed_duration_since(time);
+    if elapsed.num_minutes() < 1 {
+        "just now".to_owned()
+    } else if elapsed.num_hours() < 1 {
+        format!("{}m ago", elapsed.num_minutes())
+    } else if elapsed.num_days() < 1 {
+        format!("{}h ago", elapsed.num_hours())
+    } else if elapsed.num_days() < 30 {
+        format!("{}d ago", elapsed.num_days())
+    } else {
+        time.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d")
+            .to_string()
+    }
+}
 
-    pub fn focus(&mut self, entry: Entry) {
-        self.entry = Some(entry);
+/// Buttons for the tags of an entry in the list, returns the tag clicked to filter by.
+///
+/// A row's first line is only so wide, so tags that don't fit are folded into a count
+/// rather than pushing the preview out of view. The chips take at most half of what is
+/// left of the line, the time and preview get the rest.
+fn show_tag_chips(ui: &mut egui::Ui, tags: &[String]) -> Option<String> {
+    let mut clicked = None;
+    let mut room = ui.available_width() / 2.0;
+    let mut shown = 0;
+    for tag in tags.iter().take(MAX_TAG_CHIPS) {
+        let label = egui::RichText::new(format!("#{}", truncate_chars(tag, TAG_CHIP_CHARS)));
+        let galley = egui::WidgetText::from(label.clone().small()).into_galley(
+            ui,
+            Some(egui::TextWrapMode::Extend),
+            f32::INFINITY,
+            egui::TextStyle::Button,
+        );
+        let width = galley.size().x + 2.0 * ui.spacing().button_padding.x;
+        room -= width + ui.spacing().item_spacing.x;
+        if room < 0.0 {
+            break;
+        }
+        let chip = egui::Button::new(label.small()).corner_radius(8.0);
+        if ui
+            .add(chip)
+            .on_hover_text(format!("Only show #{tag}"))
+            .clicked()
+        {
+            clicked = Some(tag.clone());
+        }
+        shown += 1;
     }
+    if let Some(rest) = tags.get(shown..).filter(|rest| !rest.is_empty()) {
+        ui.weak(format!("+{}", rest.len()))
+            .on_hover_text(rest.join(", "));
+    }
+    clicked
+}
 
-    pub(crate) fn show(&mut self, ui: &mut egui::Ui) {
+/// What there is to know about `entry` besides its text, for the info popup of its row.
+fn show_entry_info(ui: &mut egui::Ui, entry: &Entry) {
+    egui::Grid::new("entry_info").num_columns(2).show(ui, |ui| {
+        ui.label("Id");
+        // Selectable, as the list may cut it off
+        ui.add(egui::Label::new(egui::RichText::new(&entry.id).monospace()).selectable(true));
+        ui.end_row();
 
-        if let Some(entry) = &mut self.entry {
-            ui.horizontal(|ui| {
-                if ui.button("view").clicked() {
-                    self.state = EditorState::View;
-                }
-                if ui.button("edit").clicked() {
-                    self.state = EditorState::Edit;
-                }
+        ui.label("Size");
+        ui.label(format!(
+            "{} bytes, {} lines",
+            entry.text.len(),
+            entry.text.lines().count()
+        ));
+        ui.end_row();
+
+        if !entry.tags.is_empty() {
+            ui.label("Tags");
+            ui.label(entry.tags.join(", "));
+            ui.end_row();
+        }
+
+        if let Some(created_at) = &entry.created_at {
+            ui.label("Created");
+            ui.label(match (entry.created(), created_at) {
+                (Some(created), _) => created.with_timezone(&chrono::Local).to_rfc2822(),
+                (None, Timestamp::Epoch(seconds)) => format!("{seconds} (not a time)"),
+                (None, Timestamp::Text(text)) => format!("{text} (not a time)"),
             });
+            ui.end_row();
+        }
+    });
+}
 
-            match self.state {
-                EditorState::View => {
-                    let mut cache = CommonMarkCache::default();
-                    CommonMarkViewer::new().show(
-                        ui,
-                        &mut cache,
-                        &mut entry.text.as_str(),
-                    );
-                }
-                EditorState::Edit => {
-                    ui.text_edit_multiline(&mut entry.text);
+/// What was clicked among the buttons of a row of the list.
+enum RowAction {
+    View,
+    Edit,
+    Duplicate,
+    Delete,
+    Inspect,
+}
+
+/// The buttons acting on `entry` in its row of the list. The ones that write are disabled
+/// while `writing` it.
+///
+/// Only viewing and editing get a button of their own, the rest is in a menu so the row
+/// still has room for its preview in a narrow pane.
+fn show_row_actions(ui: &mut egui::Ui, entry: &Entry, writing: bool) -> Option<RowAction> {
+    let view = ui.button("view").clicked().then_some(RowAction::View);
+    let edit = ui.button("edit").clicked().then_some(RowAction::Edit);
+    let more = ui.menu_button("⋯", |ui| {
+        let duplicate = (ui.add_enabled(!writing, egui::Button::new("Duplicate")))
+            .on_hover_text("Start a new entry from this one")
+            .on_disabled_hover_text("Waiting for the server")
+            .clicked()
+            .then_some(RowAction::Duplicate);
+        let delete = (ui.add_enabled(!writing, egui::Button::new("Delete")))
+            .on_disabled_hover_text("Waiting for the server")
+            .clicked()
+            .then_some(RowAction::Delete);
+        ui.separator();
+        // Goes through egui's clipboard plumbing, which uses the browser API on web
+        if ui.button("Copy id").clicked() {
+            ui.ctx().copy_text(entry.id.clone());
+        }
+        if ui.button("Copy text").clicked() {
+            ui.ctx().copy_text(entry.text.clone());
+        }
+        ui.separator();
+        let inspect = ui.menu_button("Info", |ui| {
+            show_entry_info(ui, entry);
+            ui.button("inspect JSON")
+                .clicked()
+                .then_some(RowAction::Inspect)
+        });
+        duplicate.or(delete).or(inspect.inner.flatten())
+    });
+    (view.or(edit)).or(more.inner.flatten())
+}
+
+/// Order in which the entries are listed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+enum SortMode {
+    /// Most recently added first, i.e. reverse server order.
+    #[default]
+    Newest,
+    /// By the time the server says they were created, newest first. Entries without one
+    /// come last, newest first as well.
+    Created,
+    IdAscending,
+    IdDescending,
+    Text,
+}
+
+impl SortMode {
+    const ALL: [Self; 5] = [
+        Self::Newest,
+        Self::Created,
+        Self::IdAscending,
+        Self::IdDescending,
+        Self::Text,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Newest => "Newest first",
+            Self::Created => "Creation time",
+            Self::IdAscending => "Id ascending",
+            Self::IdDescending => "Id descending",
+            Self::Text => "Text (A-Z)",
+        }
+    }
+}
+
+/// What the visible entries were worked out from, so they are only worked out again once
+/// any of it changed rather than every frame.
+#[derive(PartialEq)]
+struct VisibleKey {
+    entries_version: u64,
+    search: String,
+    tag_filter: Option<String>,
+    sort_mode: SortMode,
+    reverse_order: bool,
+    pinned: HashSet<String>,
+}
+
+/// Whether the server answered the last health check.
+#[derive(Default)]
+enum Connectivity {
+    #[default]
+    Unknown,
+    Checking,
+    Reachable,
+    Unreachable(String),
+}
+
+/// How the last fetch of the entries went, for the debug line in the status bar.
+struct FetchStats {
+    duration: Duration,
+    /// Size of the response body, `None` if there was no response.
+    bytes: Option<usize>,
+}
+
+/// How long fetched entries are reused when switching back to their server.
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Entries as fetched from a server, see `MyceliaApp::response_cache`.
+struct CachedResponse {
+    entries: Vec<Entry>,
+    etag: Option<String>,
+    fetched: Instant,
+}
+
+/// A write to the server that is waiting for a response.
+enum WriteOperation {
+    /// Created from the editor with this key.
+    Create(u64, Entry),
+    /// Created from the quick add box, with the text typed into it.
+    QuickAdd(String),
+    Update(Entry),
+    Delete(String),
+    /// An imported entry, created unless it has an id.
+    Import(Entry),
+}
+
+/// Imported entries being sent to the server, one at a time like a bulk delete.
+struct Upload {
+    remaining: Vec<Entry>,
+    total: usize,
+    failed: usize,
+}
+
+impl WriteOperation {
+    /// Whether this writes the entry with `id`, or the new one of editor `key`.
+    fn is_for(&self, key: Option<u64>, id: &str) -> bool {
+        match self {
+            Self::Create(create_key, _) => key == Some(*create_key),
+            Self::QuickAdd(_) => false,
+            Self::Update(entry) | Self::Import(entry) => !id.is_empty() && entry.id == id,
+            Self::Delete(delete_id) => !id.is_empty() && delete_id == id,
+        }
+    }
+}
+
+/// A request that failed, kept so it can be retried from the error message.
+enum LastAction {
+    /// Fetching the entries from this URL.
+    Fetch(String),
+    Write(WriteOperation),
+}
+
+/// A fetch of the entries that is waiting for a response.
+struct RequestState {
+    /// Only the response tagged with this generation is applied, anything older is stale.
+    generation: u64,
+    url: String,
+    started: Instant,
+    /// How many times this fetch has been retried so far.
+    retries: u32,
+    /// Set while waiting to retry after a failure.
+    retry_at: Option<Instant>,
+}
+
+/// Why a request failed.
+#[derive(Debug)]
+enum FetchError {
+    /// The server couldn't be reached, or didn't answer in time.
+    Transport(String),
+    /// The server answered with a non-2xx status.
+    Status {
+        status: u16,
+        status_text: String,
+        body: String,
+        /// How long the server asked us to wait before trying again.
+        retry_after: Option<Duration>,
+    },
+    /// The server answered, but with something we can't use.
+    Parse(String),
+}
+
+impl FetchError {
+    /// Whether trying again later might succeed: 4xx responses won't change by themselves.
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(_) => true,
+            Self::Status { status, .. } => *status >= 500,
+            Self::Parse(_) => false,
+        }
+    }
+
+    /// Whether the server rejected the API key.
+    fn is_auth_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::Status {
+                status: 401 | 403,
+                ..
+            }
+        )
+    }
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transport(e) => write!(f, "Network error: {e}"),
+            Self::Parse(e) => write!(f, "{e}"),
+            Self::Status {
+                status,
+                status_text,
+                ..
+            } if self.is_auth_failure() => write!(
+                f,
+                "Authentication failed — verify your API key ({status} {status_text})"
+            ),
+            Self::Status {
+                status,
+                status_text,
+                body,
+                ..
+            } => {
+                write!(f, "{status} {status_text}")?;
+                if !body.is_empty() {
+                    write!(f, ": {body}")?;
                 }
+                Ok(())
             }
-        } else {
-            ui.label("Nothing selected");
         }
+    }
+}
+
+/// The first `max_chars` characters of `text`, appending "…" if anything was cut off.
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", text.get(..end).unwrap_or(text)),
+        None => text.to_owned(),
+    }
+}
+
+/// How long typing has to pause before the search is applied to the list.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// How many characters of an entry the list shows.
+const PREVIEW_CHARS: usize = 120;
+
+/// How many tags of an entry the list shows, and how many characters of each.
+const MAX_TAG_CHIPS: usize = 3;
+const TAG_CHIP_CHARS: usize = 16;
+
+/// The first line of `text` for the entries list, ending in "…" if anything was left out.
+/// The title from the front matter instead, if it sets one.
+fn preview(text: &str) -> String {
+    if let Some(title) = markdown::title(text) {
+        return truncate_chars(&title, PREVIEW_CHARS);
+    }
+    let mut lines = markdown::body(text).trim().lines();
+    let preview = truncate_chars(lines.next().unwrap_or_default(), PREVIEW_CHARS);
+    if lines.next().is_some() && !preview.ends_with('…') {
+        format!("{preview}…")
+    } else {
+        preview
+    }
+}
+
+/// Puts a button over the bottom right corner of the scroll area of `output` once it is
+/// scrolled down, which scrolls it back to the top.
+fn show_scroll_to_top<R>(ui: &mut egui::Ui, output: &egui::scroll_area::ScrollAreaOutput<R>) {
+    if output.state.offset.y <= 0.0 {
         return;
     }
+    let size = egui::Vec2::splat(ui.spacing().interact_size.y * 1.5);
+    // Clear of the scroll bar, which isn't part of `inner_rect`
+    let margin = egui::vec2(ui.spacing().scroll.bar_width, 0.0) + ui.spacing().item_spacing;
+    let rect = egui::Rect::from_min_size(output.inner_rect.right_bottom() - size - margin, size);
+    if ui
+        .put(rect, egui::Button::new("⏶"))
+        .on_hover_text("Scroll to the top")
+        .clicked()
+    {
+        let mut state = output.state;
+        state.offset.y = 0.0;
+        state.store(ui.ctx(), output.id);
+        ui.ctx().request_repaint();
+    }
 }
 
-impl Default for EditorComponent {
-    fn default() -> Self {
-        EditorComponent { entry: None, state: EditorState::View }
+/// Whether the browser tab is in the background.
+#[cfg(target_arch = "wasm32")]
+fn is_hidden(_ctx: &egui::Context) -> bool {
+    (web_sys::window().and_then(|window| window.document())).is_some_and(|d| d.hidden())
+}
+
+/// Whether the window is unfocused or minimized.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_hidden(ctx: &egui::Context) -> bool {
+    ctx.input(|i| {
+        let viewport = i.viewport();
+        viewport.focused == Some(false) || viewport.minimized == Some(true)
+    })
+}
+
+/// `text` in a monospace font, read-only but selectable to copy from. Unless `wrap` is set,
+/// long lines run on, for a parent that scrolls sideways.
+fn show_raw_text(ui: &mut egui::Ui, mut text: &str, wrap: bool) {
+    let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+        let job = egui::text::LayoutJob::simple(
+            text.as_str().to_owned(),
+            egui::TextStyle::Monospace.resolve(ui.style()),
+            ui.visuals().widgets.inactive.text_color(),
+            if wrap { wrap_width } else { f32::INFINITY },
+        );
+        ui.fonts_mut(|f| f.layout_job(job))
+    };
+    // A `&str` buffer can be selected and copied from, but not edited
+    ui.add(
+        egui::TextEdit::multiline(&mut text)
+            .font(egui::TextStyle::Monospace)
+            .desired_width(f32::INFINITY)
+            .layouter(&mut layouter),
+    );
+}
+
+/// Performs `request` and hands the response body, or an error, to `on_done`.
+fn fetch(request: Request, on_done: impl 'static + Send + FnOnce(Result<String, FetchError>)) {
+    fetch_response(request, move |result| {
+        on_done(result.map(|res| res.text().unwrap_or_default().to_owned()));
+    });
+}
+
+/// Like [`fetch`], but hands over the whole response when it was a success.
+fn fetch_response(
+    request: Request,
+    on_done: impl 'static + Send + FnOnce(Result<ehttp::Response, FetchError>),
+) {
+    let request_id = request.headers.get(request_id::HEADER).map(str::to_owned);
+    // Logged with the response, to look the request up on the server
+    let sent = format!("{} {}", request.method, request.url);
+    ehttp::fetch(
+        request,
+        move |result: ehttp::Result<ehttp::Response>| match result {
+            Ok(res) => {
+                if let Some(id) = &request_id {
+                    log::info!("{sent}: {} ({id})", res.status);
+                }
+                if let Some(error) = undecoded_body(&res) {
+                    on_done(Err(error));
+                } else if res.ok {
+                    on_done(Ok(res));
+                } else {
+                    on_done(Err(FetchError::Status {
+                        status: res.status,
+                        body: res.text().unwrap_or_default().to_owned(),
+                        retry_after: retry_after(&res),
+                        status_text: res.status_text,
+                    }));
+                }
+            }
+            Err(e) => {
+                if let Some(id) = &request_id {
+                    log::info!("{sent}: {e} ({id})");
+                }
+                on_done(Err(FetchError::Transport(e)));
+            }
+        },
+    );
+}
+
+/// The `Retry-After` header of `res`, given in seconds or as an HTTP date.
+///
+/// Capped at an hour, so a bogus date can't stop reloads for the rest of the session.
+/// Browsers only show the header if the server lists it in Access-Control-Expose-Headers.
+fn retry_after(res: &ehttp::Response) -> Option<Duration> {
+    const MAX: Duration = Duration::from_secs(3600);
+
+    let value = res.headers.get("Retry-After")?.trim();
+    let delay = if let Ok(seconds) = value.parse::<u64>() {
+        Duration::from_secs(seconds)
+    } else {
+        let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        // A date in the past means right away
+        (date.to_utc() - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default()
+    };
+    Some(delay.min(MAX))
+}
+
+/// Explains a response body we can't read as text, rather than letting it fail as JSON.
+///
+/// Natively ureq decodes gzip and drops the `Content-Encoding` header when it does, so a
+/// remaining header means an encoding it doesn't support. Browsers decode whatever they
+/// asked for, so there the header says nothing.
+fn undecoded_body(res: &ehttp::Response) -> Option<FetchError> {
+    let encoding = res
+        .headers
+        .get("Content-Encoding")
+        .filter(|encoding| !encoding.eq_ignore_ascii_case("identity"));
+    if let Some(encoding) = encoding.filter(|_| cfg!(not(target_arch = "wasm32"))) {
+        return Some(FetchError::Parse(format!(
+            "The server compressed its response with \"{encoding}\", which isn't supported"
+        )));
+    }
+    if res.text().is_none() && !res.bytes.is_empty() {
+        let hint = encoding.map_or_else(String::new, |e| format!(" (Content-Encoding: {e})"));
+        return Some(FetchError::Parse(format!(
+            "The server's response isn't UTF-8 text{hint}"
+        )));
+    }
+    None
+}
+
+/// A completed fetch of the entries.
+struct Fetched {
+    generation: u64,
+    /// `None` if the server said they didn't change since the last fetch.
+    entries: Result<Option<Parsed>, FetchError>,
+    /// The `ETag` the server sent along with the entries.
+    etag: Option<String>,
+    /// Size of the response body, `None` if there was no response.
+    bytes: Option<usize>,
+}
+
+/// The entries the server sent, without the records that aren't valid entries.
+struct Parsed {
+    entries: Vec<Entry>,
+    /// Why each skipped record was invalid.
+    invalid: Vec<String>,
+}
+
+/// Parses the entries the server sent.
+///
+/// Only a body that isn't a JSON array fails as a whole, a bad record is skipped instead,
+/// so one of them doesn't hide all the others. With `validate` set, records with an empty
+/// `id` or `text` count as bad too.
+fn parse_entries(
+    body: &str,
+    content_type: Option<&str>,
+    validate: bool,
+) -> Result<Parsed, FetchError> {
+    if let Some(error) = not_json(body, content_type) {
+        return Err(error);
+    }
+    let records: Vec<serde_json::Value> = serde_json::from_str(body).map_err(|e| {
+        // Show what the server sent, it might be HTML or an error object
+        let snippet = truncate_chars(body, 200);
+        FetchError::Parse(format!("Failed to parse JSON: {e}\n{snippet}"))
+    })?;
+
+    let mut parsed = Parsed {
+        entries: Vec::with_capacity(records.len()),
+        invalid: vec![],
+    };
+    for (index, record) in records.into_iter().enumerate() {
+        let problem = match serde_json::from_value::<Entry>(record) {
+            Ok(entry) if validate && entry.id.trim().is_empty() => "no id".to_owned(),
+            Ok(entry) if validate && entry.text.trim().is_empty() => {
+                format!("entry {} has no text", entry.id)
+            }
+            Ok(entry) => {
+                parsed.entries.push(entry);
+                continue;
+            }
+            Err(e) => e.to_string(),
+        };
+        log::warn!("Skipping record {index} of the response: {problem}");
+        parsed.invalid.push(format!("record {index}: {problem}"));
+    }
+    Ok(parsed)
+}
+
+/// Explains a body that isn't JSON at all, e.g. the HTML error page of a proxy, better than
+/// the JSON parser would.
+fn not_json(body: &str, content_type: Option<&str>) -> Option<FetchError> {
+    let body = body.trim_start();
+    if body.starts_with(['[', '{']) {
+        return None;
     }
+    // A server claiming JSON but sending something else isn't told apart by its type
+    let mime = (content_type.and_then(|t| t.split(';').next()))
+        .map(str::trim)
+        .filter(|mime| !mime.is_empty() && !mime.contains("json"));
+    let got = if body.is_empty() {
+        "an empty response"
+    } else {
+        mime.unwrap_or("something else")
+    };
+    let snippet = truncate_chars(body, 200);
+    Some(FetchError::Parse(format!(
+        "Expected JSON but got {got} — check the server URL in the settings\n{snippet}"
+    )))
+}
+
+/// Performs `request` and sends the response body, or an error, to `tx`.
+fn fetch_into(request: Request, tx: Sender<Result<String, FetchError>>) {
+    fetch(request, move |result| {
+        tx.send(result).ok();
+    });
 }
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
@@ -74,32 +667,266 @@ pub struct MyceliaApp {
     #[serde(skip)]
     first_frame: bool,
 
-    api_key: String,
+    settings: SettingsComponent,
+    /// The theme last passed to `set_visuals`, if any.
+    #[serde(skip)]
+    applied_dark_mode: Option<bool>,
+    /// The zoom factor last seen, to notice egui's shortcuts changing it.
+    #[serde(skip)]
+    applied_zoom: Option<f32>,
+    /// The `remote_images` setting last applied to the image loaders, if any.
+    #[serde(skip)]
+    applied_remote_images: Option<bool>,
 
-    editor_component: EditorComponent,
+    editors: Editors,
 
     #[serde(skip)]
     view_entry: Option<Entry>,
+    /// Id of `view_entry`, persisted in its place so the entry is shown again on launch.
+    viewed_id: Option<String>,
+    /// The entry the page URL was last made to link to, on web.
+    #[serde(skip)]
+    linked_id: Option<String>,
+    /// Show the markdown source in the view pane instead of rendering it.
+    view_raw: bool,
+    /// Wrap the lines of raw text at the width of the pane, rather than scrolling sideways.
+    wrap_raw: bool,
+    /// In a window too narrow for both panes, show the view pane instead of the list.
+    #[serde(skip)]
+    show_view_pane: bool,
+    /// Show the viewed entry in a large window, for reading long entries.
+    #[serde(skip)]
+    full_screen: bool,
+    /// Entry whose JSON is shown, to check what the server sent.
+    #[serde(skip)]
+    inspect: Option<Entry>,
+    /// Heading of the viewed entry to scroll to, picked in the table of contents.
+    #[serde(skip)]
+    scroll_to_heading: Option<usize>,
+
+    /// Id of the entry waiting for delete confirmation.
+    #[serde(skip)]
+    confirm_delete: Option<String>,
+
+    /// Ids of the entries ticked in the list.
+    #[serde(skip)]
+    selected: HashSet<String>,
+    /// Ids of the entries whose whole text the list shows, instead of just the first line.
+    expanded: HashSet<String>,
+    /// Ids of the entries listed first, whatever the sort order.
+    pinned: HashSet<String>,
+    /// Set while asking whether to delete the selected entries.
+    #[serde(skip)]
+    confirm_bulk_delete: bool,
+    /// Set while asking whether to quit with unsaved changes.
+    #[serde(skip)]
+    confirm_quit: bool,
+    /// Set once quitting was confirmed, so the close isn't held up again.
+    #[serde(skip)]
+    quit_confirmed: bool,
+    /// Ids of selected entries still to be deleted, one at a time.
+    #[serde(skip)]
+    bulk_delete: Option<Vec<String>>,
+    /// Imported entries still to be sent to the server, which would drop them on the next
+    /// fetch otherwise.
+    #[serde(skip)]
+    upload: Option<Upload>,
+
+    /// Used by the view pane. Kept across frames on purpose: rebuilding it every repaint
+    /// throws away all the layout and image work for the markdown being viewed.
+    #[serde(skip)]
+    markdown_cache: CommonMarkCache,
+
+    /// What is typed into the quick add box.
+    #[serde(skip)]
+    quick_add: String,
+
+    /// What is typed into the search box.
+    #[serde(skip)]
+    search: String,
+    /// Only entries matching this are listed, see `SearchIndex::search`. Trails `search`
+    /// until typing pauses, so the list isn't filtered again on every keystroke.
+    #[serde(skip)]
+    applied_search: String,
+    /// When `search` last changed, if it wasn't applied yet.
+    #[serde(skip)]
+    search_changed: Option<Instant>,
+    /// Only entries with this tag are listed.
+    #[serde(skip)]
+    tag_filter: Option<String>,
+    /// Counts the changes to `entries`, for what is worked out from them.
+    #[serde(skip)]
+    entries_version: u64,
+    /// The indices `visible_entries` returned last, and what they were worked out from.
+    #[serde(skip)]
+    visible: Option<(VisibleKey, Rc<[usize]>)>,
+
+    sort_mode: SortMode,
+    /// List in the opposite order of `sort_mode`, e.g. oldest first.
+    reverse_order: bool,
+
+    /// Zero-based page of the list being shown.
+    #[serde(skip)]
+    page: usize,
+    page_size: usize,
+
+    /// Share of the width given to the list, the view pane gets the rest.
+    split_ratio: f32,
+
+    /// How far the list is scrolled down, so a restart doesn't jump back to the top.
+    list_offset: f32,
+    /// Height of the scrolled part of the list last frame.
+    #[serde(skip)]
+    list_viewport_height: f32,
+    /// Position in the visible entries highlighted for keyboard navigation. Unrelated to
+    /// the ticked entries in `selected`.
+    #[serde(skip)]
+    selected_index: Option<usize>,
+    /// Offset the list is scrolled to on the next frame.
+    #[serde(skip)]
+    restore_list_offset: Option<f32>,
+
+    /// Whether the last request was rejected because of the API key.
+    #[serde(skip)]
+    auth_failed: bool,
 
     #[serde(skip)]
     text: Option<Result<String, String>>,
+    /// The request whose error `text` shows, if it can be made again.
     #[serde(skip)]
+    last_action: Option<LastAction>,
+    /// Persisted as well, so the last fetched entries can be browsed offline.
     entries: Vec<Entry>,
+    /// Rebuilt wherever `entries` change.
+    #[serde(skip)]
+    search_index: SearchIndex,
+    /// Whether `entries` were fetched this session, rather than restored from storage.
+    #[serde(skip)]
+    fresh: bool,
+    /// The `ETag` of `entries`, sent back so the server can answer "not modified".
+    #[serde(skip)]
+    etag: Option<String>,
+    /// Why the last fetch skipped records, so polling doesn't notify about the same ones
+    /// again and again.
+    #[serde(skip)]
+    skipped_records: Vec<String>,
+    /// The last entries fetched by URL and `Authorization`, so switching between profiles
+    /// doesn't have to wait for the server every time.
+    #[serde(skip)]
+    response_cache: HashMap<(String, String), CachedResponse>,
+
+    /// Bumped for every fetch of the entries, so late responses can be told apart.
+    #[serde(skip)]
+    generation: u64,
+    #[serde(skip)]
+    request: Option<RequestState>,
+    #[serde(skip)]
+    tx: Sender<Fetched>,
+    #[serde(skip)]
+    rx: Receiver<Fetched>,
+    /// When the entries were last fetched successfully.
+    #[serde(skip)]
+    last_fetch: Option<chrono::DateTime<chrono::Local>>,
+    /// When the last fetch of the entries completed, successfully or not.
+    #[serde(skip)]
+    last_completed: Option<Instant>,
+    /// The server answered 429 Too Many Requests, no fetch is made before this.
+    #[serde(skip)]
+    rate_limited_until: Option<Instant>,
+    #[serde(skip)]
+    last_stats: Option<FetchStats>,
+    #[serde(skip)]
+    import_rx: Option<Receiver<Result<String, String>>>,
+    /// Imported entries waiting for the user to decide what to do with duplicate ids.
+    #[serde(skip)]
+    pending_import: Option<Vec<Entry>>,
+    /// Writes waiting for a response, there can be several with multiple editors open.
+    #[serde(skip)]
+    writes: Vec<(WriteOperation, Receiver<Result<String, FetchError>>)>,
+    #[serde(skip)]
+    notifications: Notifications,
+    #[serde(skip)]
+    error_log: ErrorLog,
+
+    #[serde(skip)]
+    palette: CommandPalette,
 
     #[serde(skip)]
-    rx: Option<Receiver<Result<String, String>>>,
+    connectivity: Connectivity,
+    #[serde(skip)]
+    health_rx: Option<Receiver<Result<String, FetchError>>>,
 }
 
 impl Default for MyceliaApp {
     fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
         Self {
             first_frame: true,
-            api_key: "Insert api key".to_owned(),
-            editor_component: Default::default(),
+            settings: Default::default(),
+            applied_dark_mode: None,
+            applied_zoom: None,
+            applied_remote_images: None,
+            editors: Editors::default(),
+            auth_failed: false,
             text: None,
+            last_action: None,
             view_entry: None,
+            viewed_id: None,
+            linked_id: None,
+            view_raw: false,
+            wrap_raw: true,
+            full_screen: false,
+            show_view_pane: false,
+            inspect: None,
+            scroll_to_heading: None,
+            confirm_delete: None,
+            selected: HashSet::new(),
+            expanded: HashSet::new(),
+            pinned: HashSet::new(),
+            confirm_bulk_delete: false,
+            confirm_quit: false,
+            quit_confirmed: false,
+            bulk_delete: None,
+            upload: None,
+            markdown_cache: CommonMarkCache::default(),
+            quick_add: String::new(),
+            search: String::new(),
+            applied_search: String::new(),
+            search_changed: None,
+            tag_filter: None,
+            entries_version: 0,
+            visible: None,
+            sort_mode: SortMode::default(),
+            reverse_order: false,
+            page: 0,
+            page_size: 50,
+            split_ratio: 0.5,
+            list_offset: 0.0,
+            list_viewport_height: 0.0,
+            selected_index: None,
+            restore_list_offset: None,
             entries: vec![],
-            rx: None,
+            search_index: SearchIndex::default(),
+            fresh: false,
+            generation: 0,
+            request: None,
+            tx,
+            rx,
+            last_fetch: None,
+            etag: None,
+            skipped_records: vec![],
+            response_cache: HashMap::new(),
+            last_completed: None,
+            rate_limited_until: None,
+            last_stats: None,
+            import_rx: None,
+            pending_import: None,
+            writes: vec![],
+            notifications: Notifications::default(),
+            error_log: ErrorLog::default(),
+            palette: CommandPalette::default(),
+            connectivity: Connectivity::Unknown,
+            health_rx: None,
         }
     }
 }
@@ -112,138 +939,2175 @@ impl MyceliaApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
+        let mut app: Self = if let Some(storage) = cc.storage {
             eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default()
         } else {
             Default::default()
+        };
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+        browser_keys::install();
+        if let Some(id) = deep_link::linked_entry() {
+            app.linked_id = Some(id.clone());
+            app.viewed_id = Some(id);
+            app.show_view_pane = true;
         }
+        app.entries_changed();
+        // Cleared only once a fetch says the entry is gone, the cached ones may be outdated
+        app.view_entry = (app.viewed_id.as_ref())
+            .and_then(|id| app.entries.iter().find(|e| &e.id == id))
+            .cloned();
+        // Otherwise a narrow window would hide the restored entry behind the list
+        app.show_view_pane = app.viewed_id.is_some();
+        app.editors.hold_drafts();
+        app.restore_list_offset = Some(app.list_offset);
+        app
     }
 
-    fn make_request(&mut self, url: &str) {
-        let url = url.to_string();
-        let api_key = self.api_key.clone();
+    /// Throws away all state, in memory and in storage, leaving the app as on its first launch.
+    fn clear_local_data(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        log::info!("Clearing local data");
+        *self = Self::default();
+        // Window positions and sizes are persisted by egui itself
+        ctx.memory_mut(|memory| *memory = Default::default());
+        // Overwrite right away, in case the stored state is what wedged the app
+        if let Some(storage) = frame.storage_mut() {
+            eframe::set_value(storage, eframe::APP_KEY, self);
+            storage.flush();
+        }
+    }
+
+    /// Forgets the API keys and everything fetched with them, in memory and in storage.
+    /// Open editors and drafts are kept, saving them only needs a key again.
+    fn log_out(&mut self, frame: &mut eframe::Frame) {
+        log::info!("Logging out");
+        self.abort_request();
+        self.settings.log_out();
+        self.entries.clear();
+        self.entries_changed();
+        // Keyed by the authorization header as well, which holds the key
+        self.response_cache.clear();
+        self.etag = None;
+        self.upload = None;
+        self.selected.clear();
+        self.selected_index = None;
+        self.view_entry = None;
+        self.viewed_id = None;
+        self.inspect = None;
+        self.full_screen = false;
+        self.auth_failed = false;
+        self.last_fetch = None;
+        // Auto-refresh only starts again after logging back in and fetching
+        self.last_completed = None;
+        self.fresh = false;
+        // Overwrite right away, rather than leave the key on disk until the next auto-save
+        if let Some(storage) = frame.storage_mut() {
+            eframe::set_value(storage, eframe::APP_KEY, self);
+            storage.flush();
+        }
+        self.check_api_key();
+    }
+
+    /// Saves all entries as pretty JSON, in the format importing expects.
+    fn export_entries(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| format!("Failed to serialize entries: {e}"))?;
+        file_io::save_file("mycelia-entries.json", &json)
+    }
+
+    /// Asks for a JSON file of entries, like the ones `export_entries` writes.
+    fn import_entries(&mut self) {
         let (tx, rx) = mpsc::channel();
+        self.import_rx = Some(rx);
+        file_io::open_file(move |result| {
+            tx.send(result).ok();
+        });
+    }
 
-        self.rx = Some(rx);
+    /// Whether an entry with this server id is loaded, entries without an id never clash.
+    fn has_entry(&self, id: &str) -> bool {
+        !id.is_empty() && self.entries.iter().any(|e| e.id == id)
+    }
 
-        let request = Request {
-            headers: ehttp::Headers::new(&[("Authorization", &format!("Bearer {}", api_key))]),
-            ..Request::get(url)
-        };
-        ehttp::fetch(
-            request,
-            move |result: ehttp::Result<ehttp::Response>| match result {
-                Ok(res) => {
-                    if res.ok {
-                        let _ = tx.send(Ok(res.text().unwrap().to_string()));
-                    } else {
-                        let _ = tx.send(Err(res.text().unwrap().to_string()));
-                    }
+    /// Merges `imported` into `entries`, overwriting or skipping entries with the same id,
+    /// and uploads the ones that were merged.
+    fn merge_entries(&mut self, imported: Vec<Entry>, overwrite: bool) {
+        let mut upload = vec![];
+        for entry in imported {
+            match self
+                .entries
+                .iter_mut()
+                .find(|e| !e.id.is_empty() && e.id == entry.id)
+            {
+                Some(existing) if overwrite => {
+                    *existing = entry.clone();
+                    upload.push(entry);
                 }
-                Err(res) => {
-                    let _ = tx.send(Err(res.to_string()));
+                Some(_) => {}
+                None => {
+                    // Its id may be one of another server, this one assigns its own
+                    upload.push(Entry {
+                        id: String::new(),
+                        ..entry.clone()
+                    });
+                    self.entries.push(entry);
                 }
-            },
-        );
+            }
+        }
+        self.entries_changed();
+        if !upload.is_empty() {
+            // Popped from the back, so they are created in the order of the file
+            upload.reverse();
+            self.upload = Some(Upload {
+                total: upload.len(),
+                remaining: upload,
+                failed: 0,
+            });
+        }
     }
-}
 
-impl eframe::App for MyceliaApp {
-    /// Called by the framework to save state before shutdown.
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+    /// Sends `entry`, which was imported, to the server.
+    fn upload_entry(&mut self, entry: &Entry) {
+        if entry.id.is_empty() {
+            let url = self.settings.messages_url();
+            self.write_entry("POST", url, entry, WriteOperation::Import(entry.clone()));
+        } else {
+            let url = self.settings.message_url(&entry.id);
+            self.write_entry("PUT", url, entry, WriteOperation::Import(entry.clone()));
+        }
     }
 
-    /// Called each time the UI needs repainting, which may be many times per second.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.first_frame {
-            self.first_frame = false;
-            self.make_request("https://mycelia.nel.re/api/messages");
+    /// Sends the next imported entry once the previous one completed, and fetches the
+    /// entries again after the last, for the ids the server assigned.
+    fn advance_upload(&mut self) {
+        if (self.writes.iter()).any(|(operation, _)| matches!(operation, WriteOperation::Import(_)))
+        {
+            return;
+        }
+        if self.upload.is_some() && !self.check_api_key() {
+            self.upload = None;
+            self.notifications
+                .error("Imported entries aren't on the server, the next reload drops them");
+            return;
+        }
+        let Some(upload) = &mut self.upload else {
+            return;
+        };
+        if let Some(entry) = upload.remaining.pop() {
+            self.upload_entry(&entry);
+            return;
         }
 
-        // Check if request completed
-        if self.text.is_none() {
-            if let Some(rx) = &self.rx {
-                if let Ok(result) = rx.try_recv() {
-                    match result {
-                        Ok(body) => {
-                            self.entries.clear();
-                            match serde_json::from_str::<Vec<Entry>>(&body) {
-                                Ok(entries) => {
-                                    self.entries = entries;
-                                    self.text = Some(Ok("".to_string()));
-                                }
-                                Err(e) => {
-                                    self.text = Some(Err(format!("Failed to parse JSON: {}", e)));
-                                }
-                            }
-                        }
-                        Err(e) => self.text = Some(Err(e)),
-                    }
-                    self.rx = None;
-                }
-            }
+        let (total, failed) = (upload.total, upload.failed);
+        self.upload = None;
+        if failed == 0 {
+            self.notifications
+                .success(format!("Imported {total} entries"));
+        } else {
+            self.notifications
+                .error(format!("Imported {} of {total} entries", total - failed));
         }
+        self.reload();
+    }
 
-        ctx.set_visuals(egui::Visuals::dark());
+    fn show_import_dialog(&mut self, ctx: &egui::Context) {
+        let Some(imported) = &self.pending_import else {
+            return;
+        };
+        let duplicates = imported
+            .iter()
+            .filter(|entry| self.has_entry(&entry.id))
+            .count();
+        let total = imported.len();
 
-        // There is nothing in the top bar for web (yet)
-        let is_web = cfg!(target_arch = "wasm32");
-        if !is_web {
-            egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-                egui::MenuBar::new().ui(ui, |ui| {
-                    ui.menu_button("File", |ui| {
-                        if ui.button("Quit").clicked() {
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        egui::Window::new("Import")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{duplicates} of {total} imported entries already exist."
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Overwrite").clicked() {
+                        if let Some(imported) = self.pending_import.take() {
+                            self.merge_entries(imported, true);
                         }
-                    });
-                    ui.add_space(16.0);
+                    }
+                    if ui.button("Skip").clicked() {
+                        if let Some(imported) = self.pending_import.take() {
+                            self.merge_entries(imported, false);
+                        }
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.pending_import = None;
+                    }
                 });
             });
-        }
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Mycelia");
+    /// Whether requests can be sent, otherwise tells the user to enter an API key first.
+    fn check_api_key(&mut self) -> bool {
+        if self.settings.has_api_key() || self.settings.mock_mode {
+            true
+        } else {
+            self.set_error(
+                "Enter your API key in the settings (⚙) to talk to the server".to_owned(),
+            );
+            false
+        }
+    }
 
-            ui.horizontal(|ui| {
-                ui.label("API key: ");
-                ui.text_edit_singleline(&mut self.api_key);
+    /// Opens the text files dropped onto the window as new entries, to look over before
+    /// saving them.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        let mut opened = 0;
+        for file in &dropped {
+            match file_io::read_dropped(file) {
+                Ok(text) => {
+                    self.editors.create(Entry {
+                        text,
+                        ..Default::default()
+                    });
+                    opened += 1;
+                }
+                Err(e) => self.notifications.error(e),
+            }
+        }
+        match opened {
+            0 => {}
+            1 => self
+                .notifications
+                .success("Opened the dropped file, save it to create an entry"),
+            n => self.notifications.success(format!(
+                "Opened {n} dropped files, save them to create entries"
+            )),
+        }
+
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let rect = ctx.content_rect();
+            let painter =
+                ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, "drop".into()));
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop text files to create entries from them",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    /// Starts over with the server of the profile just switched to, the entries of the
+    /// previous one are dropped so they can't be sent to the new one. Open editors are
+    /// left alone.
+    fn switched_server(&mut self) {
+        self.abort_request();
+        self.entries.clear();
+        self.entries_changed();
+        self.selected.clear();
+        self.selected_index = None;
+        self.view_entry = None;
+        self.etag = None;
+        // They were imported into the list of the other server
+        self.upload = None;
+        self.rate_limited_until = None;
+        self.auth_failed = false;
+        self.last_action = None;
+        self.text = None;
+        self.page = 0;
+        self.make_request(&self.settings.messages_url(), false);
+        self.check_connectivity();
+    }
+
+    /// Fetches all entries again.
+    fn reload(&mut self) {
+        self.text = None;
+        self.page = 0;
+        self.make_request(&self.settings.messages_url(), true);
+    }
+
+    fn cache_key(&self, url: &str) -> (String, String) {
+        (url.to_owned(), self.settings.authorization())
+    }
+
+    /// Keeps `entries` as the response for `url`, dropping responses that are too old.
+    fn cache_response(&mut self, url: &str) {
+        if self.settings.mock_mode {
+            return;
+        }
+        self.response_cache
+            .retain(|_, cached| cached.fetched.elapsed() < CACHE_TTL);
+        let cached = CachedResponse {
+            entries: self.entries.clone(),
+            etag: self.etag.clone(),
+            fetched: Instant::now(),
+        };
+        self.response_cache.insert(self.cache_key(url), cached);
+    }
+
+    /// Shows the entries cached for `url` if they aren't too old, returns whether it did.
+    fn show_cached(&mut self, url: &str) -> bool {
+        let Some(cached) = self.response_cache.get(&self.cache_key(url)) else {
+            return false;
+        };
+        if cached.fetched.elapsed() >= CACHE_TTL {
+            return false;
+        }
+        log::debug!(
+            "Showing the entries fetched {:?} ago",
+            cached.fetched.elapsed()
+        );
+        let (entries, etag) = (cached.entries.clone(), cached.etag.clone());
+        // Polling goes on from when they were fetched
+        let fetched = cached.fetched;
+        self.abort_request();
+        self.entries_fetched(entries, etag);
+        self.last_completed = Some(fetched);
+        // The status bar tells how old they are, not when they were shown
+        let age = chrono::TimeDelta::from_std(fetched.elapsed()).unwrap_or_default();
+        self.last_fetch = Some(chrono::Local::now() - age);
+        true
+    }
+
+    /// Stops waiting for the fetch in flight, if any.
+    ///
+    /// ehttp can't cancel a request, so this only makes sure its response is ignored: the
+    /// request may still complete, server side as well.
+    fn abort_request(&mut self) {
+        if let Some(request) = self.request.take() {
+            log::debug!("Aborting request {}", request.generation);
+            // Not strictly needed with `request` gone, but keeps every generation unique
+            self.generation += 1;
+            self.last_completed = Some(Instant::now());
+        }
+    }
+
+    /// Starts fetching the entries from `url`, unless recently fetched ones can be shown
+    /// and `force` isn't set.
+    ///
+    /// Any fetch that is still in flight is superseded: its response will be discarded.
+    fn make_request(&mut self, url: &str, force: bool) {
+        if !force && self.show_cached(url) {
+            return;
+        }
+        if !self.check_api_key() {
+            return;
+        }
+        if let Some(wait) = self.rate_limit_remaining() {
+            log::debug!("Not fetching, rate limited for another {wait:?}");
+            return;
+        }
+        self.generation += 1;
+        let generation = self.generation;
+        self.request = Some(RequestState {
+            generation,
+            url: url.to_owned(),
+            started: Instant::now(),
+            retries: 0,
+            retry_at: None,
+        });
+        self.send_request();
+    }
+
+    /// How much longer the server wants us to wait before fetching again, if at all.
+    fn rate_limit_remaining(&self) -> Option<Duration> {
+        self.rate_limited_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|wait| !wait.is_zero())
+    }
+
+    /// Sends the pending fetch (again).
+    fn send_request(&mut self) {
+        let authorization = self.settings.authorization();
+        let timeout = self.settings.timeout();
+        let Some(state) = &mut self.request else {
+            return;
+        };
+        state.started = Instant::now();
+        state.retry_at = None;
+
+        if self.settings.mock_mode {
+            let fetched = Fetched {
+                generation: state.generation,
+                entries: Ok(Some(Parsed {
+                    entries: mock::mock_entries(),
+                    invalid: vec![],
+                })),
+                etag: None,
+                bytes: None,
+            };
+            self.tx.send(fetched).ok();
+            return;
+        }
+
+        let mut headers = vec![("Authorization", authorization.as_str())];
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match", etag));
+        }
+        let request = Request {
+            headers: self.settings.headers(&headers),
+            timeout: Some(timeout),
+            ..Request::get(&state.url)
+        };
+        let generation = state.generation;
+        let validate = self.settings.validate_entries;
+        let tx = self.tx.clone();
+        // Parsed in the callback rather than in `update`, so a big response doesn't stall a frame
+        fetch_response(request, move |result| {
+            let (entries, etag, bytes) = match result {
+                Ok(res) => (
+                    parse_entries(res.text().unwrap_or_default(), res.content_type(), validate)
+                        .map(Some),
+                    // Browsers only show it if the server lists it in Access-Control-Expose-Headers
+                    res.headers.get("ETag").map(str::to_owned),
+                    Some(res.bytes.len()),
+                ),
+                Err(FetchError::Status { status: 304, .. }) => (Ok(None), None, Some(0)),
+                Err(e) => {
+                    let bytes = match &e {
+                        FetchError::Status { body, .. } => Some(body.len()),
+                        _ => None,
+                    };
+                    (Err(e), None, bytes)
+                }
+            };
+            tx.send(Fetched {
+                generation,
+                entries,
+                etag,
+                bytes,
+            })
+            .ok();
+        });
+    }
+
+    /// Checks whether the server answers at all, without needing an API key.
+    fn check_connectivity(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        self.connectivity = Connectivity::Checking;
+        self.health_rx = Some(rx);
+        let request = Request {
+            headers: self.settings.headers(&[]),
+            timeout: Some(self.settings.timeout()),
+            ..Request::head(&self.settings.base_url)
+        };
+        fetch_into(request, tx);
+    }
+
+    /// Sends `entry` to the server: new entries (without an id) are posted and the
+    /// server assigns them an id, existing ones are put to `/api/messages/{id}`.
+    /// `key` is the editor it is saved from.
+    fn save_entry(&mut self, key: u64, entry: &Entry) {
+        if entry.id.is_empty() {
+            self.write_entry(
+                "POST",
+                self.settings.messages_url(),
+                entry,
+                WriteOperation::Create(key, entry.clone()),
+            );
+        } else {
+            let url = self.settings.message_url(&entry.id);
+            self.write_entry("PUT", url, entry, WriteOperation::Update(entry.clone()));
+        }
+    }
+
+    fn write_entry(&mut self, method: &str, url: String, entry: &Entry, operation: WriteOperation) {
+        if !self.check_api_key() {
+            return;
+        }
+        let body = match serde_json::to_vec(entry) {
+            Ok(body) => body,
+            Err(e) => {
+                self.set_error(format!("Failed to serialize entry: {e}"));
+                return;
+            }
+        };
+        let (tx, rx) = mpsc::channel();
+
+        self.writes.push((operation, rx));
+
+        let request = Request {
+            method: method.to_owned(),
+            headers: self.settings.headers(&[
+                ("Accept", "*/*"),
+                ("Content-Type", "application/json"),
+                ("Authorization", &self.settings.authorization()),
+            ]),
+            ..Request::post(url, body)
+        };
+        fetch_into(request, tx);
+    }
+
+    fn delete_entry(&mut self, id: String) {
+        if !self.check_api_key() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+
+        let request = Request {
+            method: "DELETE".to_owned(),
+            headers: self
+                .settings
+                .headers(&[("Authorization", &self.settings.authorization())]),
+            ..Request::get(self.settings.message_url(&id))
+        };
+        self.writes.push((WriteOperation::Delete(id), rx));
+        fetch_into(request, tx);
+    }
+
+    /// Shows `error` to the user.
+    fn request_failed(&mut self, error: &FetchError, action: LastAction) {
+        self.auth_failed = error.is_auth_failure();
+        if self.auth_failed {
+            // The key lives in the settings, so bring them up to fix it
+            self.settings.open();
+        }
+        self.set_error(error.to_string());
+        self.last_action = Some(action);
+    }
+
+    /// Shows `message` as error, one that can't be retried.
+    fn set_error(&mut self, message: String) {
+        self.error_log.push(message.clone());
+        self.text = Some(Err(message));
+        self.last_action = None;
+    }
+
+    /// Makes the request that failed again, with the current settings.
+    fn retry(&mut self) {
+        let Some(action) = self.last_action.take() else {
+            return;
+        };
+        self.text = None;
+        match action {
+            LastAction::Fetch(url) => self.make_request(&url, true),
+            LastAction::Write(WriteOperation::Create(key, entry)) => self.save_entry(key, &entry),
+            LastAction::Write(WriteOperation::QuickAdd(text)) => {
+                if self.quick_add == text {
+                    self.quick_add.clear();
+                }
+                self.quick_add_entry(text);
+            }
+            LastAction::Write(WriteOperation::Update(entry)) => {
+                let url = self.settings.message_url(&entry.id);
+                self.write_entry("PUT", url, &entry, WriteOperation::Update(entry.clone()));
+            }
+            LastAction::Write(WriteOperation::Delete(id)) => self.delete_entry(id),
+            LastAction::Write(WriteOperation::Import(entry)) => self.upload_entry(&entry),
+        }
+    }
+
+    fn show_bulk_delete_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_bulk_delete {
+            return;
+        }
+
+        egui::Window::new("Delete selected?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(match self.selected.len() {
+                    1 => "Delete 1 selected entry?".to_owned(),
+                    n => format!("Delete {n} selected entries?"),
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Yes").clicked() {
+                        self.confirm_bulk_delete = false;
+                        self.bulk_delete = Some(self.selected.iter().cloned().collect());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_bulk_delete = false;
+                    }
+                });
+            });
+    }
+
+    /// Holds up closing the window while there are unsaved changes, until confirmed.
+    ///
+    /// Catches the Quit menu as well as the window manager's close button, both end up as
+    /// a close request. Browsers don't ask, a tab just goes away.
+    fn guard_quit(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| i.viewport().close_requested())
+            && !self.quit_confirmed
+            && self.editors.has_unsaved()
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.confirm_quit = true;
+        }
+        if !self.confirm_quit {
+            return;
+        }
+
+        egui::Window::new("Quit?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Some entries have unsaved changes. Quit anyway?");
+                ui.weak("They are offered as drafts on the next launch.");
+                ui.horizontal(|ui| {
+                    if ui.button("Quit").clicked() {
+                        self.confirm_quit = false;
+                        self.quit_confirmed = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_quit = false;
+                    }
+                });
+            });
+    }
+
+    /// Sends the next delete of a bulk delete once the previous one completed, so the
+    /// server gets one at a time.
+    fn advance_bulk_delete(&mut self) {
+        if self
+            .writes
+            .iter()
+            .any(|(operation, _)| matches!(operation, WriteOperation::Delete(_)))
+        {
+            return;
+        }
+        let Some(remaining) = &mut self.bulk_delete else {
+            return;
+        };
+        if let Some(id) = remaining.pop() {
+            self.delete_entry(id);
+            return;
+        }
+
+        self.bulk_delete = None;
+        let total = self.selected.len();
+        let left = self.selected.iter().filter(|id| self.has_entry(id)).count();
+        if left == 0 {
+            self.notifications
+                .success(format!("Deleted {total} entries"));
+        } else {
+            self.notifications
+                .error(format!("Deleted {} of {total} entries", total - left));
+        }
+        self.selected.clear();
+    }
+
+    fn show_delete_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(id) = self.confirm_delete.clone() else {
+            return;
+        };
+
+        egui::Window::new("Delete?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("Delete entry {id}?"));
+                ui.horizontal(|ui| {
+                    if ui.button("Yes").clicked() {
+                        self.confirm_delete = None;
+                        self.delete_entry(id);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_delete = None;
+                    }
+                });
             });
+    }
+
+    /// Applies a completed fetch, unless a newer fetch superseded it.
+    fn apply_response(&mut self, fetched: Fetched) {
+        let Fetched {
+            generation,
+            entries: result,
+            etag,
+            bytes,
+        } = fetched;
+        let Some(request) = self
+            .request
+            .as_mut()
+            .filter(|request| request.generation == generation)
+        else {
+            log::debug!("Discarding stale response of request {generation}");
+            return;
+        };
+
+        if let Err(FetchError::Status {
+            status: 429,
+            retry_after,
+            ..
+        }) = &result
+        {
+            // Without a Retry-After, wait as long as a server would typically ask for
+            let delay = retry_after.unwrap_or(Duration::from_secs(60));
+            let until = Instant::now() + delay;
+            self.rate_limited_until = Some(until);
+            if request.retries < self.settings.retries {
+                log::debug!("Request {generation} rate limited, retrying in {delay:?}");
+                request.retries += 1;
+                request.retry_at = Some(until);
+                return;
+            }
+        }
+        if let Err(e) = &result {
+            if e.is_retryable() && request.retries < self.settings.retries {
+                // Back off exponentially: 200ms, 400ms, 800ms, …
+                let delay = Duration::from_millis(200 << request.retries.min(16));
+                log::debug!("Request {generation} failed ({e}), retrying in {delay:?}");
+                request.retries += 1;
+                request.retry_at = Some(Instant::now() + delay);
+                return;
+            }
+        }
+        let url = std::mem::take(&mut request.url);
+        self.last_stats = Some(FetchStats {
+            duration: request.started.elapsed(),
+            bytes,
+        });
+        self.request = None;
+        self.last_completed = Some(Instant::now());
+
+        // On failure the cached entries stay around
+        match result {
+            Ok(None) => {
+                log::debug!("Entries not modified since the last fetch");
+                if let Some(cached) = self.response_cache.get_mut(&self.cache_key(&url)) {
+                    cached.fetched = Instant::now();
+                }
+                self.fresh = true;
+                self.last_fetch = Some(chrono::Local::now());
+                self.auth_failed = false;
+                self.text = Some(Ok(String::new()));
+            }
+            Ok(Some(Parsed { entries, invalid })) => {
+                if let Some(first) = invalid.first()
+                    && invalid != self.skipped_records
+                {
+                    self.notifications.error(format!(
+                        "Skipped {} invalid {} ({first}{})",
+                        invalid.len(),
+                        if invalid.len() == 1 {
+                            "entry"
+                        } else {
+                            "entries"
+                        },
+                        if invalid.len() > 1 { ", …" } else { "" }
+                    ));
+                }
+                self.skipped_records = invalid;
+                self.entries_fetched(entries, etag);
+                self.cache_response(&url);
+            }
+            Err(e) => self.request_failed(&e, LastAction::Fetch(url)),
+        }
+    }
+
+    /// Replaces `entries` by ones fetched from the server.
+    fn entries_fetched(&mut self, entries: Vec<Entry>, etag: Option<String>) {
+        // The old position means nothing if the list changed a lot
+        let (old, new) = (self.entries.len(), entries.len());
+        if old.abs_diff(new) > old.max(new) / 10 {
+            self.restore_list_offset = Some(0.0);
+        }
+        self.entries = entries;
+        self.entries_changed();
+        self.resolve_viewed_entry();
+        let ids: HashSet<&str> = self.entries.iter().map(|e| e.id.as_str()).collect();
+        self.expanded.retain(|id| ids.contains(id.as_str()));
+        self.etag = etag;
+        self.fresh = true;
+        self.last_fetch = Some(chrono::Local::now());
+        self.auth_failed = false;
+        self.text = Some(Ok(String::new()));
+    }
+
+    /// Keeps the page URL linking to the viewed entry, or the one waiting to be fetched.
+    fn sync_deep_link(&mut self) {
+        let viewed = self.view_entry.as_ref().map(|e| &e.id);
+        let viewed = viewed.or(self.viewed_id.as_ref());
+        if viewed != self.linked_id.as_ref() {
+            deep_link::link_entry(viewed.map(String::as_str));
+            self.linked_id = viewed.cloned();
+        }
+    }
+
+    /// Replaces the viewed entry, or the one viewed last session, by its copy in `entries`.
+    /// Stops viewing it if it is gone.
+    fn resolve_viewed_entry(&mut self) {
+        let viewed = self.view_entry.as_ref().map(|e| &e.id);
+        let Some(id) = viewed.or(self.viewed_id.as_ref()) else {
+            return;
+        };
+        self.view_entry = self.entries.iter().find(|e| &e.id == id).cloned();
+        self.viewed_id = self.view_entry.as_ref().map(|e| e.id.clone());
+        if self.view_entry.is_none() {
+            // Rather than leave a narrow window on an empty view pane
+            self.show_view_pane = false;
+        }
+    }
+
+    /// To be called after changing `entries`.
+    fn entries_changed(&mut self) {
+        self.entries_version += 1;
+        self.search_index
+            .rebuild(&self.entries, self.entries_version);
+    }
+
+    /// Indices into `entries` matching `query`, see `SearchIndex::search`, ascending.
+    fn search(&self, query: &str) -> Vec<usize> {
+        self.search_index
+            .search(&self.entries, self.entries_version, query)
+    }
+
+    /// Indices into `entries` matching the search, in the chosen sort order. Only worked out
+    /// again once something they depend on changed, see `VisibleKey`.
+    ///
+    /// Sorting a view rather than `entries` itself means a reload doesn't undo it.
+    fn visible_entries(&mut self) -> Rc<[usize]> {
+        let key = VisibleKey {
+            entries_version: self.entries_version,
+            search: self.applied_search.clone(),
+            tag_filter: self.tag_filter.clone(),
+            sort_mode: self.sort_mode,
+            reverse_order: self.reverse_order,
+            pinned: self.pinned.clone(),
+        };
+        if let Some((cached, visible)) = &self.visible
+            && *cached == key
+        {
+            return visible.clone();
+        }
+        let visible: Rc<[usize]> = self.filter_and_sort().into();
+        self.visible = Some((key, visible.clone()));
+        visible
+    }
+
+    /// Indices into `entries` matching the search and tag filter, in the chosen sort order.
+    fn filter_and_sort(&self) -> Vec<usize> {
+        let mut indices = self.search(&self.applied_search);
+        if let Some(tag) = &self.tag_filter {
+            indices.retain(|&i| self.entries.get(i).is_some_and(|e| e.tags.contains(tag)));
+        }
+
+        let entries = &self.entries;
+        match self.sort_mode {
+            SortMode::Newest => indices.reverse(),
+            SortMode::Created => {
+                // Stable, so the reversal decides among entries without a time
+                indices.reverse();
+                indices.sort_by_cached_key(|&i| {
+                    std::cmp::Reverse(entries.get(i).and_then(Entry::created))
+                });
+            }
+            SortMode::IdAscending => indices.sort_by_key(|&i| entries.get(i).map(|e| &e.id)),
+            SortMode::IdDescending => {
+                indices.sort_by_key(|&i| std::cmp::Reverse(entries.get(i).map(|e| &e.id)));
+            }
+            SortMode::Text => {
+                indices.sort_by_cached_key(|&i| entries.get(i).map(|e| e.text.to_lowercase()));
+            }
+        }
+        if self.reverse_order {
+            indices.reverse();
+        }
+        // Stable, so pinned entries keep the sort order among themselves
+        indices.sort_by_key(|&i| !entries.get(i).is_some_and(|e| self.pinned.contains(&e.id)));
+        indices
+    }
+
+    /// Explains why the list is empty, and what to do about it.
+    fn show_empty_list(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(32.0);
+        ui.vertical_centered(|ui| {
+            if self.request.is_some() && self.entries.is_empty() {
+                ui.spinner();
+                ui.label("Loading…");
+            } else if !self.entries.is_empty() {
+                ui.label("No entries match the search or tag filter");
+            } else if self.last_fetch.is_none() {
+                ui.label("Nothing fetched yet — click reload to get your entries");
+                if ui.button("reload").clicked() {
+                    self.reload();
+                }
+            } else {
+                ui.label("No entries yet — click new to get started");
+                if ui.button("new").clicked() {
+                    self.editors.edit(Entry::default());
+                }
+            }
+        });
+    }
+
+    /// `visible` are the indices from `visible_entries`.
+    fn show_entries(&mut self, ui: &mut egui::Ui, visible: &[usize]) {
+        ui.horizontal(|ui| {
+            let search = ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Search"));
+            if search.changed() {
+                self.search_changed = Some(Instant::now());
+            }
+            egui::ComboBox::from_id_salt("sort_mode")
+                .selected_text(self.sort_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in SortMode::ALL {
+                        ui.selectable_value(&mut self.sort_mode, mode, mode.label());
+                    }
+                });
+            ui.toggle_value(&mut self.reverse_order, "⇅")
+                .on_hover_text("Reverse the order, e.g. to list the oldest entries first");
+            if ui
+                .add_enabled(!visible.is_empty(), egui::Button::new("copy all"))
+                .on_hover_text("Copy the listed entries as one markdown document")
+                .clicked()
+            {
+                ui.ctx().copy_text(self.entries_as_markdown(visible));
+                self.notifications
+                    .success(format!("Copied {} entries", visible.len()));
+            }
+            if let Some(tag) = &self.tag_filter {
+                if ui
+                    .button(format!("#{tag} ✖"))
+                    .on_hover_text("Show entries with any tag")
+                    .clicked()
+                {
+                    self.tag_filter = None;
+                    self.page = 0;
+                }
+            }
+        });
+
+        let moved = self.handle_list_keys(ui, visible);
+
+        let pages = visible.len().div_ceil(self.page_size.max(1)).max(1);
+        self.page = self.page.min(pages - 1);
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.page > 0, egui::Button::new("◀"))
+                .clicked()
+            {
+                self.page -= 1;
+            }
+            ui.label(format!("page {} of {pages}", self.page + 1));
+            if ui
+                .add_enabled(self.page + 1 < pages, egui::Button::new("▶"))
+                .clicked()
+            {
+                self.page += 1;
+            }
+            ui.add(
+                egui::DragValue::new(&mut self.page_size)
+                    .range(1..=1000)
+                    .suffix(" per page"),
+            );
+            if ui.button("expand all").clicked() {
+                let ids = visible.iter().filter_map(|&index| self.entries.get(index));
+                self.expanded.extend(ids.map(|entry| entry.id.clone()));
+            }
+            if ui.button("collapse all").clicked() {
+                self.expanded.clear();
+            }
+        });
+        let first_row = self.page * self.page_size.max(1);
+        let visible = visible
+            .chunks(self.page_size.max(1))
+            .nth(self.page)
+            .unwrap_or_default();
+
+        if visible.is_empty() {
+            self.show_empty_list(ui);
+            return;
+        }
+
+        self.show_list(ui, visible, first_row, moved);
+    }
+
+    /// The rows of the entries at `visible`, the first of which is row `first_row` of the
+    /// list. `moved` is whether the highlighted row changed, to scroll it into view.
+    fn show_list(&mut self, ui: &mut egui::Ui, visible: &[usize], first_row: usize, moved: bool) {
+        let any_expanded = (visible.iter())
+            .filter_map(|&index| self.entries.get(index))
+            .any(|entry| self.expanded.contains(&entry.id));
+        let row_height = ui.spacing().interact_size.y;
+        if let Some(index) = self.selected_index.filter(|_| moved && !any_expanded) {
+            // Scroll just enough to bring the highlighted row into view
+            let spacing = row_height + ui.spacing().item_spacing.y;
+            let top = index.saturating_sub(first_row) as f32 * spacing;
+            if top < self.list_offset {
+                self.restore_list_offset = Some(top);
+            } else if top + row_height > self.list_offset + self.list_viewport_height {
+                self.restore_list_offset = Some(top + row_height - self.list_viewport_height);
+            }
+        }
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_salt("entries")
+            .auto_shrink(false);
+        if let Some(offset) = self.restore_list_offset.take() {
+            scroll_area = scroll_area.vertical_scroll_offset(offset);
+        }
+        let output = if any_expanded {
+            // Expanded rows are higher than a line, so all rows of the page are laid out
+            scroll_area.show(ui, |ui| {
+                for (row, &index) in visible.iter().enumerate() {
+                    let row = first_row + row;
+                    let rect = ui.scope(|ui| self.show_row(ui, row, index)).response.rect;
+                    if moved && self.selected_index == Some(row) {
+                        ui.scroll_to_rect(rect, None);
+                    }
+                }
+            })
+        } else {
+            // Only the rows in view are laid out, so thousands of entries stay cheap. That
+            // requires every row to be exactly one line high.
+            scroll_area.show_rows(ui, row_height, visible.len(), |ui, rows| {
+                for row in rows {
+                    if let Some(&index) = visible.get(row) {
+                        self.show_row(ui, first_row + row, index);
+                    }
+                }
+            })
+        };
+        self.list_offset = output.state.offset.y;
+        self.list_viewport_height = output.inner_rect.height();
+        show_scroll_to_top(ui, &output);
+    }
+
+    /// The entries at `visible`, in that order, as one document: a heading with the id of
+    /// each, separated by rules.
+    fn entries_as_markdown(&self, visible: &[usize]) -> String {
+        visible
+            .iter()
+            .filter_map(|&index| self.entries.get(index))
+            .map(|entry| format!("## {}\n\n{}\n", entry.id, entry.text.trim()))
+            .collect::<Vec<_>>()
+            .join("\n---\n\n")
+    }
+
+    /// Moves `selected_index` with the arrow keys, views the highlighted entry on Enter or
+    /// V and edits it on E. Returns whether the highlight moved.
+    fn handle_list_keys(&mut self, ui: &egui::Ui, visible: &[usize]) -> bool {
+        let Some(last) = visible.len().checked_sub(1) else {
+            self.selected_index = None;
+            return false;
+        };
+        // Keep it in bounds when the list shrinks
+        self.selected_index = self.selected_index.map(|index| index.min(last));
+
+        // Leave the keys to text boxes, e.g. the search box
+        if ui.ctx().wants_keyboard_input() {
+            return false;
+        }
+        let (up, down, view, edit) = ui.input(|i| {
+            // Not while a modifier is held, Ctrl+V is still paste
+            let letter = |key| i.modifiers.is_none() && i.key_pressed(key);
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter) || letter(egui::Key::V),
+                letter(egui::Key::E),
+            )
+        });
+
+        let highlighted = self.selected_index.and_then(|index| visible.get(index));
+        if let Some(entry) = highlighted.and_then(|&index| self.entries.get(index)) {
+            if view {
+                self.view_entry = Some(entry.clone());
+                self.show_view_pane = true;
+            }
+            if edit {
+                self.editors.edit(entry.clone());
+            }
+        }
+        if !up && !down {
+            return false;
+        }
+        let index = match self.selected_index {
+            None => 0,
+            Some(index) if up => index.saturating_sub(1),
+            Some(index) => (index + 1).min(last),
+        };
+        self.selected_index = Some(index);
+        self.page = index / self.page_size.max(1);
+        true
+    }
+
+    /// Shows `entries[index]` as the `row`th line of the visible entries.
+    fn show_row(&mut self, ui: &mut egui::Ui, row: usize, index: usize) {
+        let Some(entry) = self.entries.get(index) else {
+            return;
+        };
+
+        let fill = if self.selected_index == Some(row) {
+            ui.visuals().selection.bg_fill
+        } else if row % 2 == 1 {
+            ui.visuals().faint_bg_color
+        } else {
+            egui::Color32::TRANSPARENT
+        };
+        let preview = preview(&entry.text);
+        let expanded = self.expanded.contains(&entry.id);
+        egui::Frame::new().fill(fill).show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            if !expanded {
+                ui.set_height(ui.spacing().interact_size.y);
+            }
+            ui.horizontal(|ui| {
+                // Disabled rather than left out when there is nothing more, so rows line up
+                let more = preview != entry.text.trim();
+                let toggle = egui::Button::new(if expanded { "▼" } else { "▶" }).frame(false);
+                if ui.add_enabled(more || expanded, toggle).clicked() {
+                    if expanded {
+                        self.expanded.remove(&entry.id);
+                    } else {
+                        self.expanded.insert(entry.id.clone());
+                    }
+                }
+                let pinned = self.pinned.contains(&entry.id);
+                let star = egui::Button::new(if pinned { "★" } else { "☆" }).frame(false);
+                let hover = if pinned { "Unpin" } else { "Pin to the top" };
+                if ui.add(star).on_hover_text(hover).clicked() {
+                    if pinned {
+                        self.pinned.remove(&entry.id);
+                    } else {
+                        self.pinned.insert(entry.id.clone());
+                    }
+                }
+                let mut selected = self.selected.contains(&entry.id);
+                if ui.checkbox(&mut selected, "").changed() {
+                    if selected {
+                        self.selected.insert(entry.id.clone());
+                    } else {
+                        self.selected.remove(&entry.id);
+                    }
+                }
+                // Until the server answered, so a double click doesn't write twice
+                let writing = (self.writes.iter()).any(|(write, _)| write.is_for(None, &entry.id));
+                match show_row_actions(ui, entry, writing) {
+                    Some(RowAction::View) => {
+                        self.view_entry = Some(entry.clone());
+                        self.show_view_pane = true;
+                        self.selected_index = Some(row);
+                    }
+                    Some(RowAction::Edit) => self.editors.edit(entry.clone()),
+                    Some(RowAction::Duplicate) => self.editors.duplicate(entry),
+                    Some(RowAction::Delete) => self.confirm_delete = Some(entry.id.clone()),
+                    Some(RowAction::Inspect) => self.inspect = Some(entry.clone()),
+                    None => {}
+                }
+
+                if let Some(tag) = show_tag_chips(ui, &entry.tags) {
+                    self.tag_filter = Some(tag);
+                    self.page = 0;
+                }
+
+                if let Some(created) = entry.created() {
+                    ui.weak(relative_time(created))
+                        .on_hover_text(created.with_timezone(&chrono::Local).to_rfc2822());
+                }
 
-            if ui.button("reload").clicked() {
-                self.text = None;
-                self.make_request("https://mycelia.nel.re/api/messages");
+                // Laid out here rather than for all entries, so only the rows shown pay for it
+                let text: egui::WidgetText = if self.applied_search.is_empty() {
+                    preview.into()
+                } else {
+                    let matching = search::Matching::new(&self.applied_search);
+                    find::highlight_matches(ui, &preview, matching.ranges(&preview)).into()
+                };
+                ui.add(egui::Label::new(text).truncate());
+            });
+            if expanded {
+                ui.add(egui::Label::new(entry.text.trim()).wrap());
             }
+        });
+    }
 
+    /// The list and the view pane side by side, with a draggable divider between them.
+    /// Windows narrower than `NARROW_WIDTH`, e.g. phones, get one pane at a time instead.
+    fn show_panes(&mut self, ui: &mut egui::Ui, visible: &[usize]) {
+        const DIVIDER_WIDTH: f32 = 8.0;
+        // Enough of either pane stays visible to grab the divider again
+        const MIN_RATIO: f32 = 0.15;
+        const NARROW_WIDTH: f32 = 600.0;
+
+        if ui.available_width() < NARROW_WIDTH {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.show_view_pane, false, "List");
+                ui.selectable_value(&mut self.show_view_pane, true, "View");
+            });
             ui.separator();
+            if self.show_view_pane {
+                self.show_view(ui);
+            } else {
+                self.show_entries(ui, visible);
+            }
+            return;
+        }
+
+        let rect = ui.available_rect_before_wrap();
+        let width = (rect.width() - DIVIDER_WIDTH).max(0.0);
+        let list_width = width * self.split_ratio.clamp(MIN_RATIO, 1.0 - MIN_RATIO);
 
-            ui.columns(2, |ui| {
-                egui::ScrollArea::vertical().show(&mut ui[0], |ui| {
-                    if self.entries.is_empty() {
-                        ui.label("Loading...");
-                    }
-                    egui::Grid::new("entries")
-                        .num_columns(2)
-                        .max_col_width(ui.available_width()) // Why is this needed?
-                        .striped(true)
-                        .show(ui, |ui| {
-                            for entry in self.entries.iter().rev() {
-                                if ui.button("open").clicked() {
-                                    self.editor_component.focus(entry.clone());
-                                }
-
-                                ui.label(&entry.text);
-                                ui.end_row();
+        let list_rect = egui::Rect::from_min_size(rect.min, egui::vec2(list_width, rect.height()));
+        let divider_rect = egui::Rect::from_min_size(
+            list_rect.right_top(),
+            egui::vec2(DIVIDER_WIDTH, rect.height()),
+        );
+        let view_rect = egui::Rect::from_min_max(divider_rect.right_top(), rect.max);
+
+        let divider = ui
+            .interact(divider_rect, ui.id().with("divider"), egui::Sense::drag())
+            .on_hover_cursor(egui::CursorIcon::ResizeHorizontal);
+        if divider.dragged() && width > 0.0 {
+            if let Some(pointer) = divider.interact_pointer_pos() {
+                let ratio = (pointer.x - rect.left() - DIVIDER_WIDTH / 2.0) / width;
+                self.split_ratio = ratio.clamp(MIN_RATIO, 1.0 - MIN_RATIO);
+            }
+        }
+        let stroke = ui.style().interact(&divider).fg_stroke;
+        ui.painter()
+            .vline(divider_rect.center().x, rect.y_range(), stroke);
+
+        ui.scope_builder(egui::UiBuilder::new().max_rect(list_rect), |ui| {
+            self.show_entries(ui, visible);
+        });
+        ui.scope_builder(egui::UiBuilder::new().max_rect(view_rect), |ui| {
+            self.show_view(ui);
+        });
+        ui.allocate_rect(rect, egui::Sense::hover());
+    }
+
+    /// A one-line box to jot down an entry without opening an editor.
+    fn show_quick_add(&mut self, ui: &mut egui::Ui) {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let empty = self.quick_add.trim().is_empty();
+            let add = ui.add_enabled(!empty, egui::Button::new("add")).clicked();
+            let input = ui.add(
+                egui::TextEdit::singleline(&mut self.quick_add)
+                    .hint_text("Quick add, Enter to save")
+                    .desired_width(f32::INFINITY),
+            );
+            let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if !(add || submitted) || empty || !self.check_api_key() {
+                return;
+            }
+            let text = std::mem::take(&mut self.quick_add);
+            self.quick_add_entry(text);
+            if submitted {
+                // Ready for the next one
+                input.request_focus();
+            }
+        });
+    }
+
+    /// Creates an entry from what was typed into the quick add box.
+    fn quick_add_entry(&mut self, text: String) {
+        let entry = Entry {
+            text: text.trim().to_owned(),
+            ..Default::default()
+        };
+        let url = self.settings.messages_url();
+        self.write_entry("POST", url, &entry, WriteOperation::QuickAdd(text));
+    }
+
+    /// The error of the last action, if it failed, with a button to try it again.
+    fn show_error(&mut self, ui: &mut egui::Ui) {
+        let Some(Err(e)) = &self.text else {
+            return;
+        };
+        let mut retry = false;
+        ui.horizontal(|ui| {
+            if self.last_action.is_some() {
+                retry = ui.button("retry").clicked();
+            }
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        });
+        if retry {
+            self.retry();
+        }
+    }
+
+    fn show_toolbar(&mut self, ui: &mut egui::Ui) {
+        let loading = self.request.is_some();
+
+        ui.horizontal(|ui| {
+            if ui.button("⚙").on_hover_text("Settings").clicked() {
+                self.settings.toggle();
+            }
+            ui.heading("Mycelia");
+            // Writes too, the spinner also keeps repainting until their responses are in
+            if loading || !self.writes.is_empty() {
+                ui.spinner();
+            }
+            if !self.fresh && !self.entries.is_empty() {
+                ui.weak("cached").on_hover_text(
+                    "Showing the entries from the last session until a reload succeeds",
+                );
+            }
+        });
+
+        let rate_limited = self.rate_limit_remaining();
+        if let Some(wait) = rate_limited {
+            // Keep the countdown going
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Rate limited, retry in {:.0}s", wait.as_secs_f32().ceil()),
+            );
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !loading
+                        && rate_limited.is_none()
+                        && (self.settings.has_api_key() || self.settings.mock_mode),
+                    egui::Button::new(if loading { "reloading…" } else { "reload" }),
+                )
+                .on_hover_text("Fetch the entries again (F5)")
+                .clicked()
+            {
+                self.reload();
+            }
+            if loading
+                && ui
+                    .button("abort")
+                    .on_hover_text("Stop waiting for the server")
+                    .clicked()
+            {
+                self.abort_request();
+            }
+            if ui.button("new").clicked() {
+                self.editors.edit(Entry::default());
+            }
+            if ui.button("export").clicked() {
+                if let Err(e) = self.export_entries() {
+                    self.set_error(e);
+                }
+            }
+            if ui.button("import").clicked() {
+                self.import_entries();
+            }
+            let selected = self.selected.len();
+            if ui
+                .add_enabled(
+                    selected > 0 && self.bulk_delete.is_none(),
+                    egui::Button::new(format!("delete selected ({selected})")),
+                )
+                .clicked()
+            {
+                self.confirm_bulk_delete = true;
+            }
+        });
+    }
+
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::Reload => {
+                if self.request.is_none() {
+                    self.reload();
+                }
+            }
+            Action::NewEntry => self.editors.edit(Entry::default()),
+            Action::Export => {
+                if let Err(e) = self.export_entries() {
+                    self.set_error(e);
+                }
+            }
+            Action::Import => self.import_entries(),
+            Action::ToggleTheme => self.settings.dark_mode = !self.settings.dark_mode,
+            Action::OpenSettings => self.settings.open(),
+        }
+    }
+
+    fn show_status_bar(&mut self, ui: &mut egui::Ui, visible: usize) {
+        ui.horizontal(|ui| {
+            let size = ui.spacing().interact_size.y * 0.5;
+            let (color, status) = match &self.connectivity {
+                Connectivity::Unknown => (ui.visuals().weak_text_color(), "Not checked".to_owned()),
+                Connectivity::Checking => (ui.visuals().weak_text_color(), "Checking…".to_owned()),
+                Connectivity::Reachable => (egui::Color32::GREEN, "Server reachable".to_owned()),
+                Connectivity::Unreachable(e) => (egui::Color32::RED, format!("Unreachable: {e}")),
+            };
+            if matches!(self.connectivity, Connectivity::Checking) {
+                // Also keeps repainting until the answer comes in
+                ui.add(egui::Spinner::new().size(size))
+                    .on_hover_text(status);
+            } else {
+                let (rect, dot) =
+                    ui.allocate_exact_size(egui::Vec2::splat(size), egui::Sense::click());
+                ui.painter().circle_filled(rect.center(), size / 2.0, color);
+                if dot
+                    .on_hover_text(format!("{status}\nClick to check again"))
+                    .clicked()
+                {
+                    self.check_connectivity();
+                }
+            }
+
+            let last_fetch = self.last_fetch.map_or_else(
+                || "never".to_owned(),
+                |time| time.format("%H:%M:%S").to_string(),
+            );
+            ui.label(format!("{visible} entries • last updated {last_fetch}"));
+
+            if self.settings.debug_info {
+                if let Some(stats) = &self.last_stats {
+                    let size = match stats.bytes {
+                        Some(bytes) if bytes < 1024 => format!("{bytes} B"),
+                        Some(bytes) => format!("{:.1} KiB", bytes as f64 / 1024.0),
+                        None => "no response".to_owned(),
+                    };
+                    ui.weak(format!(
+                        "• last fetch took {} ms, {size}",
+                        stats.duration.as_millis()
+                    ));
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let errors = format!("errors ({})", self.error_log.len());
+                if ui
+                    .selectable_label(self.error_log.is_open(), errors)
+                    .on_hover_text("Show the recent errors")
+                    .clicked()
+                {
+                    self.error_log.toggle();
+                }
+                egui::warn_if_debug_build(ui);
+            });
+        });
+    }
+
+    /// Renders the entry picked with "view".
+    fn show_view(&mut self, ui: &mut egui::Ui) {
+        let Some(entry) = self.view_entry.as_mut() else {
+            ui.label("Nothing selected");
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("copy id").clicked() {
+                ui.ctx().copy_text(entry.id.clone());
+            }
+            if ui.button("copy text").clicked() {
+                ui.ctx().copy_text(entry.text.clone());
+            }
+            ui.checkbox(&mut self.view_raw, "raw")
+                .on_hover_text("Show the markdown as written");
+            ui.add_enabled(
+                self.view_raw,
+                egui::Checkbox::new(&mut self.wrap_raw, "wrap"),
+            )
+            .on_hover_text("Wrap long lines instead of scrolling sideways");
+            if ui
+                .button("full screen")
+                .on_hover_text("Read the entry in a large window (Esc to close)")
+                .clicked()
+            {
+                self.full_screen = true;
+            }
+        });
+        ui.separator();
+
+        let headings = if self.view_raw {
+            vec![]
+        } else {
+            markdown::headings(&entry.text)
+        };
+        if !headings.is_empty() {
+            egui::CollapsingHeader::new("Contents")
+                .id_salt("toc")
+                .show(ui, |ui| {
+                    for (index, heading) in headings.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.add_space((heading.level - 1) as f32 * 12.0);
+                            if ui.link(&heading.title).clicked() {
+                                self.scroll_to_heading = Some(index);
                             }
                         });
+                    }
                 });
+            ui.separator();
+        }
+        let scroll_to = self.scroll_to_heading.take();
+
+        let unchanged = entry.text.clone();
+        let sideways = self.view_raw && !self.wrap_raw;
+        let scroll_area = egui::ScrollArea::new([sideways, true]).id_salt("view");
+        let output = scroll_area.show(ui, |ui| {
+            if self.view_raw {
+                show_raw_text(ui, &entry.text, self.wrap_raw);
+                false
+            } else {
+                if let Some(heading) = scroll_to.and_then(|index| headings.get(index)) {
+                    markdown::scroll_to_heading(
+                        ui,
+                        &mut self.markdown_cache,
+                        &entry.text,
+                        self.settings.markdown(),
+                        heading,
+                    );
+                }
+                markdown::show_mut(
+                    ui,
+                    &mut self.markdown_cache,
+                    &mut entry.text,
+                    self.settings.markdown(),
+                )
+            }
+        });
+        show_scroll_to_top(ui, &output);
 
-                self.editor_component.show(&mut ui[1]);
+        if output.inner {
+            let saved = self.entries.iter().find(|e| e.id == entry.id);
+            let saved = saved.map_or(unchanged.as_str(), |e| e.text.as_str());
+            if !self.editors.tick(entry, &unchanged, saved) {
+                entry.text = unchanged;
+                self.notifications
+                    .error("Save or discard the changes in the editor before ticking items");
+            }
+        }
+    }
+
+    /// Ticks in the view pane are kept by the entry's editor. Once that is closed without
+    /// saving, the view goes back to the saved text.
+    fn drop_unsaved_ticks(&mut self) {
+        let Some(viewed) = &self.view_entry else {
+            return;
+        };
+        let stale = !self.editors.is_editing(&viewed.id)
+            && (self.entries.iter()).any(|e| e.id == viewed.id && e.text != viewed.text);
+        if stale {
+            self.resolve_viewed_entry();
+        }
+    }
+
+    /// The JSON of the inspected entry, read-only.
+    fn show_inspector(&mut self, ctx: &egui::Context) {
+        let Some(entry) = &self.inspect else {
+            return;
+        };
+        let json = serde_json::to_string_pretty(entry)
+            .unwrap_or_else(|e| format!("Failed to serialize entry: {e}"));
+
+        let mut open = true;
+        egui::Window::new(format!("Entry {}", entry.id))
+            .id(egui::Id::new("inspector"))
+            .open(&mut open)
+            .default_size([480.0, 320.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("copy").clicked() {
+                        ui.ctx().copy_text(json.clone());
+                    }
+                    ui.checkbox(&mut self.wrap_raw, "wrap");
+                });
+                egui::ScrollArea::both().show(ui, |ui| {
+                    show_raw_text(ui, &json, self.wrap_raw);
+                });
             });
+        if !open {
+            self.inspect = None;
+        }
+    }
 
-            ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-                egui::warn_if_debug_build(ui);
+    /// The viewed entry at a comfortable reading width, over the rest of the app.
+    fn show_full_screen(&mut self, ctx: &egui::Context) {
+        const MAX_WIDTH: f32 = 800.0;
+
+        let Some(entry) = self.view_entry.as_ref().filter(|_| self.full_screen) else {
+            self.full_screen = false;
+            return;
+        };
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) && !ctx.wants_keyboard_input() {
+            self.full_screen = false;
+            return;
+        }
+
+        let screen = ctx.content_rect();
+        let size = egui::vec2(
+            (screen.width() - 48.0).min(MAX_WIDTH),
+            screen.height() * 0.85,
+        );
+        let mut open = true;
+        egui::Window::new(preview(&entry.text))
+            .id(egui::Id::new("full_screen_view"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .fixed_size(size)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::ScrollArea::new([self.view_raw && !self.wrap_raw, true])
+                    .id_salt("full_screen")
+                    .show(ui, |ui| {
+                        if self.view_raw {
+                            show_raw_text(ui, &entry.text, self.wrap_raw);
+                        } else {
+                            markdown::show(
+                                ui,
+                                &mut self.markdown_cache,
+                                &entry.text,
+                                self.settings.markdown(),
+                            );
+                        }
+                    });
             });
+        self.full_screen = open;
+    }
+
+    /// Applies `ui_scale` on launch, and keeps it in step with egui's zoom shortcuts.
+    fn sync_ui_scale(&mut self, ctx: &egui::Context) {
+        let zoom = ctx.zoom_factor();
+        match self.applied_zoom {
+            None => ctx.set_zoom_factor(self.settings.ui_scale),
+            Some(applied) if (applied - zoom).abs() > f32::EPSILON => {
+                self.settings.ui_scale = zoom;
+            }
+            Some(_) => {}
+        }
+        self.applied_zoom = Some(ctx.zoom_factor());
+    }
+
+    /// Applies the search once typing paused for `SEARCH_DEBOUNCE`.
+    fn debounce_search(&mut self, ctx: &egui::Context) {
+        if let Some(wait) = self.apply_paused_search(Instant::now()) {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    /// Applies the search if typing paused for `SEARCH_DEBOUNCE` by `now`. Otherwise
+    /// returns how much longer it has to pause.
+    fn apply_paused_search(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.search_changed?);
+        if elapsed < SEARCH_DEBOUNCE {
+            return Some(SEARCH_DEBOUNCE - elapsed);
+        }
+        self.search_changed = None;
+        if self.applied_search != self.search {
+            self.applied_search.clone_from(&self.search);
+            self.page = 0;
+        }
+        None
+    }
+
+    /// Reloads once `poll_interval` has passed since the last fetch completed.
+    fn poll_periodically(&mut self, ctx: &egui::Context) {
+        let (Some(interval), Some(last_completed)) =
+            (self.settings.poll_interval, self.last_completed)
+        else {
+            return;
+        };
+        // Polling without a key would only fail with the same error every frame
+        if self.request.is_some() || !(self.settings.has_api_key() || self.settings.mock_mode) {
+            return;
+        }
+
+        let interval = match (is_hidden(ctx), self.settings.hidden_polling) {
+            (false, _) | (true, HiddenPolling::Continue) => interval,
+            (true, HiddenPolling::Slow) => interval * 5,
+            (true, HiddenPolling::Pause) => {
+                // Looks again in a while, to poll as soon as the app is shown again
+                ctx.request_repaint_after(Duration::from_secs(1));
+                return;
+            }
+        };
+
+        let elapsed = last_completed.elapsed();
+        if elapsed >= interval {
+            // Not `reload`, that would jump back to the first page every poll
+            self.make_request(&self.settings.messages_url(), true);
+        } else {
+            ctx.request_repaint_after(interval - elapsed);
+        }
+    }
+
+    /// Applies the results of any requests that completed since the last frame.
+    fn poll_responses(&mut self) {
+        if let Some(request) = &self.request {
+            if let Some(retry_at) = request.retry_at {
+                if Instant::now() >= retry_at {
+                    self.send_request();
+                }
+            } else if request.started.elapsed() > self.settings.timeout() {
+                // Give up on a fetch the server is taking too long to answer
+                let error = FetchError::Transport("Request timed out".to_owned());
+                self.tx
+                    .send(Fetched {
+                        generation: request.generation,
+                        entries: Err(error),
+                        etag: None,
+                        bytes: None,
+                    })
+                    .ok();
+            }
+        }
+
+        // Check if request completed
+        while let Ok(fetched) = self.rx.try_recv() {
+            self.apply_response(fetched);
+        }
+
+        // Check if an import was read
+        if let Some(result) = self.import_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.import_rx = None;
+            match result.and_then(|json| {
+                serde_json::from_str::<Vec<Entry>>(&json)
+                    .map_err(|e| format!("Failed to parse JSON: {e}"))
+            }) {
+                Ok(imported) => {
+                    let has_duplicates = imported.iter().any(|entry| self.has_entry(&entry.id));
+                    if has_duplicates {
+                        self.pending_import = Some(imported);
+                    } else {
+                        self.merge_entries(imported, false);
+                    }
+                }
+                Err(e) => self.set_error(e),
+            }
+        }
+
+        // Check if the health check completed
+        if let Some(result) = self.health_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.health_rx = None;
+            self.connectivity = match result {
+                // Any answer means the server is up, even an error status
+                Ok(_) | Err(FetchError::Status { .. } | FetchError::Parse(_)) => {
+                    Connectivity::Reachable
+                }
+                Err(FetchError::Transport(e)) => Connectivity::Unreachable(e),
+            };
+        }
+
+        // Check if writes completed
+        for (operation, rx) in std::mem::take(&mut self.writes) {
+            match rx.try_recv() {
+                Ok(result) => self.write_completed(operation, result),
+                Err(mpsc::TryRecvError::Empty) => self.writes.push((operation, rx)),
+                Err(mpsc::TryRecvError::Disconnected) => {}
+            }
+        }
+        self.advance_bulk_delete();
+        self.advance_upload();
+    }
+
+    fn write_completed(&mut self, operation: WriteOperation, result: Result<String, FetchError>) {
+        match (operation, result) {
+            (WriteOperation::Create(key, _), Ok(_)) => {
+                // The server assigned an id we don't know yet, so fetch everything again
+                self.editors.close(key);
+                self.notifications.success("Created");
+                self.reload();
+            }
+            (WriteOperation::QuickAdd(_), Ok(_)) => {
+                self.notifications.success("Added");
+                self.reload();
+            }
+            (WriteOperation::Update(entry), Ok(_)) => {
+                self.editors.saved(&entry);
+                if let Some(viewed) = self.view_entry.as_mut().filter(|e| e.id == entry.id) {
+                    *viewed = entry.clone();
+                }
+                if let Some(existing) = self.entries.iter_mut().find(|e| e.id == entry.id) {
+                    *existing = entry;
+                }
+                self.entries_changed();
+                // Would show the old text when switching back to this server
+                self.response_cache.clear();
+                self.notifications.success("Saved");
+            }
+            // Reported once all are sent
+            (WriteOperation::Import(_), Ok(_)) => {}
+            (WriteOperation::Delete(id), Ok(_)) => {
+                self.entries.retain(|e| e.id != id);
+                self.entries_changed();
+                // Would bring the entry back when switching to this server again
+                self.response_cache.clear();
+                if self.view_entry.as_ref().is_some_and(|e| e.id == id) {
+                    self.view_entry = None;
+                    self.viewed_id = None;
+                }
+                self.editors.close_entry(&id);
+                self.pinned.remove(&id);
+                // A bulk delete reports once it is done
+                if self.bulk_delete.is_none() {
+                    self.notifications.success("Deleted");
+                }
+            }
+            (operation, Err(e)) => {
+                let action = match &operation {
+                    WriteOperation::Create(..) => "Creating",
+                    WriteOperation::QuickAdd(text) => {
+                        // Give the text back rather than losing it, unless more was typed
+                        if self.quick_add.is_empty() {
+                            self.quick_add.clone_from(text);
+                        }
+                        "Adding"
+                    }
+                    WriteOperation::Update(_) => "Saving",
+                    WriteOperation::Delete(_) => "Deleting",
+                    WriteOperation::Import(_) => {
+                        if let Some(upload) = &mut self.upload {
+                            upload.failed += 1;
+                        }
+                        "Importing"
+                    }
+                };
+                self.notifications.error(format!("{action} failed: {e}"));
+                self.request_failed(&e, LastAction::Write(operation));
+            }
+        }
+    }
+}
+
+impl eframe::App for MyceliaApp {
+    /// Called by the framework to save state before shutdown.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if let Some(entry) = &self.view_entry {
+            self.viewed_id = Some(entry.id.clone());
+        }
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+
+    /// Saves more often while there are unsaved changes, so a closed tab loses little of them.
+    fn auto_save_interval(&self) -> Duration {
+        if self.editors.is_dirty() {
+            Duration::from_secs(5)
+        } else {
+            Duration::from_secs(30)
+        }
+    }
+
+    /// Called each time the UI needs repainting, which may be many times per second.
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if self.first_frame {
+            self.first_frame = false;
+            // Without a key it would only fail, the empty list says what to do instead
+            let can_fetch = self.settings.has_api_key() || self.settings.mock_mode;
+            if self.settings.auto_fetch_on_start && can_fetch {
+                self.make_request(&self.settings.messages_url(), false);
+            }
+            self.check_connectivity();
+        }
+
+        self.poll_responses();
+        self.sync_deep_link();
+        self.handle_dropped_files(ctx);
+        self.poll_periodically(ctx);
+        self.debounce_search(ctx);
+        self.sync_ui_scale(ctx);
+
+        // F5 rather than Ctrl+R, which eframe leaves to the browser. It would leave F5 to it
+        // as well, see `browser_keys`
+        if ctx.input(|i| i.key_pressed(egui::Key::F5)) && self.request.is_none() {
+            self.reload();
+        }
+
+        // Only touch the visuals when the theme changes, not every frame
+        if self.applied_dark_mode != Some(self.settings.dark_mode) {
+            self.applied_dark_mode = Some(self.settings.dark_mode);
+            ctx.set_visuals(if self.settings.dark_mode {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+
+        if self.applied_remote_images != Some(self.settings.remote_images) {
+            self.applied_remote_images = Some(self.settings.remote_images);
+            markdown::allow_remote_images(ctx, self.settings.remote_images);
+        }
+
+        // There is nothing in the top bar for web (yet)
+        let is_web = cfg!(target_arch = "wasm32");
+        if !is_web {
+            egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+                egui::MenuBar::new().ui(ui, |ui| {
+                    ui.menu_button("File", |ui| {
+                        if ui.button("Quit").clicked() {
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                    });
+                    ui.add_space(16.0);
+                });
+            });
+        }
+
+        // Not Ctrl+K, the browser would move the focus to its search bar on web
+        let palette = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::P);
+        if ctx.input_mut(|i| i.consume_shortcut(&palette)) {
+            self.palette.toggle();
+        }
+        if let Some(action) = self.palette.show(ctx) {
+            self.run_action(action);
+        }
+
+        self.guard_quit(ctx);
+        self.show_full_screen(ctx);
+        self.show_inspector(ctx);
+        self.show_delete_confirmation(ctx);
+        self.show_bulk_delete_confirmation(ctx);
+        self.notifications.show(ctx);
+        self.show_import_dialog(ctx);
+        if self.settings.show(ctx, self.auth_failed) {
+            self.clear_local_data(ctx, frame);
+            ctx.request_repaint();
+            return;
+        }
+        if self.settings.take_log_out() {
+            self.log_out(frame);
+        }
+        if self.settings.take_profile_switched() {
+            self.switched_server();
+        }
+
+        let writes = &self.writes;
+        let writing = |key, id: &str| writes.iter().any(|(write, _)| write.is_for(Some(key), id));
+        if let Some((key, entry)) = self.editors.show(ctx, &self.settings, writing) {
+            self.save_entry(key, &entry);
+        }
+        self.drop_unsaved_ticks();
+
+        // Shared by the list and the status bar
+        let visible = self.visible_entries();
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            self.show_status_bar(ui, visible.len());
+        });
+        self.error_log.show(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.show_toolbar(ui);
+            self.show_quick_add(ui);
+            self.editors.show_draft_banner(ui);
+
+            self.show_error(ui);
+
+            ui.separator();
+
+            self.show_panes(ui, &visible);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> Entry {
+        Entry {
+            id: id.to_owned(),
+            text: format!("entry {id}"),
+            ..Entry::default()
+        }
+    }
+
+    fn fetched(generation: u64, ids: &[&str]) -> Fetched {
+        Fetched {
+            generation,
+            entries: Ok(Some(Parsed {
+                entries: ids.iter().map(|id| entry(id)).collect(),
+                invalid: vec![],
+            })),
+            etag: None,
+            bytes: None,
+        }
+    }
+
+    /// An app with fetch `generation` in flight, the ones before it superseded.
+    fn fetching(generation: u64) -> MyceliaApp {
+        let mut app = MyceliaApp::default();
+        app.generation = generation;
+        app.request = Some(RequestState {
+            generation,
+            url: app.settings.messages_url(),
+            started: Instant::now(),
+            retries: 0,
+            retry_at: None,
         });
+        app
+    }
+
+    fn ids(app: &MyceliaApp) -> Vec<&str> {
+        app.entries.iter().map(|e| e.id.as_str()).collect()
+    }
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let body = r#"[{"id": "1", "text": "spores", "owner": {"name": "nel"}}]"#;
+        let parsed =
+            parse_entries(body, Some("application/json"), true).unwrap_or_else(|e| panic!("{e:?}"));
+        let [entry] = parsed.entries.as_slice() else {
+            panic!("{} entries parsed", parsed.entries.len());
+        };
+        assert_eq!(
+            entry.extra.get("owner"),
+            Some(&serde_json::json!({"name": "nel"}))
+        );
+        assert_eq!(
+            serde_json::to_value(entry).ok(),
+            Some(serde_json::json!({"id": "1", "text": "spores", "owner": {"name": "nel"}}))
+        );
+    }
+
+    #[test]
+    fn only_the_newest_fetch_is_applied() {
+        // The superseded fetch answering last
+        let mut app = fetching(2);
+        app.tx.send(fetched(2, &["new"])).ok();
+        app.tx.send(fetched(1, &["old"])).ok();
+        app.poll_responses();
+        assert_eq!(ids(&app), ["new"]);
+        assert!(app.request.is_none());
+
+        // And first
+        let mut app = fetching(2);
+        app.tx.send(fetched(1, &["old"])).ok();
+        app.poll_responses();
+        assert!(app.entries.is_empty());
+        assert!(app.request.is_some());
+        app.tx.send(fetched(2, &["new"])).ok();
+        app.poll_responses();
+        assert_eq!(ids(&app), ["new"]);
+    }
+
+    #[test]
+    fn list_is_filtered_once_typing_pauses() {
+        let mut app = MyceliaApp {
+            entries: ["mycelium", "spore", "hypha"].map(entry).into(),
+            ..MyceliaApp::default()
+        };
+        app.entries_changed();
+        let start = Instant::now();
+        let frame = Duration::from_millis(10);
+        let mut typing = "mycelium".chars();
+        let mut visible = app.visible_entries();
+        let mut passes = 0;
+        // A key every 5 frames, faster than the debounce, then frames for a while after
+        for i in 0..100 {
+            let now = start + frame * i;
+            if i % 5 == 0
+                && let Some(c) = typing.next()
+            {
+                app.search.push(c);
+                app.search_changed = Some(now);
+            }
+            app.apply_paused_search(now);
+            // Worked out again rather than taken from the cache
+            let shown = app.visible_entries();
+            if !Rc::ptr_eq(&shown, &visible) {
+                passes += 1;
+            }
+            visible = shown;
+        }
+        assert_eq!(passes, 1);
+        assert_eq!(app.applied_search, "mycelium");
+        assert_eq!(*visible, [0]);
+    }
+
+    /// Lays out one frame of a `width` by `height` window with `add_contents` in its
+    /// central panel.
+    fn run(
+        ctx: &egui::Context,
+        width: f32,
+        height: f32,
+        mut add_contents: impl FnMut(&mut egui::Ui),
+    ) -> egui::FullOutput {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(width, height),
+            )),
+            ..Default::default()
+        };
+        ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| add_contents(ui));
+        })
+    }
+
+    /// The texts painted by `shapes`.
+    fn texts(shapes: &[egui::epaint::ClippedShape]) -> Vec<String> {
+        fn collect(shape: &egui::Shape, texts: &mut Vec<String>) {
+            match shape {
+                egui::Shape::Text(text) => texts.push(text.galley.text().to_owned()),
+                egui::Shape::Vec(shapes) => {
+                    for shape in shapes {
+                        collect(shape, texts);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut texts = vec![];
+        for clipped in shapes {
+            collect(&clipped.shape, &mut texts);
+        }
+        texts
+    }
+
+    #[test]
+    fn list_lays_out_only_the_rows_in_view() {
+        let mut app = MyceliaApp {
+            entries: (0..10_000).map(|i| entry(&i.to_string())).collect(),
+            ..MyceliaApp::default()
+        };
+        app.entries_changed();
+        let visible = app.visible_entries();
+        let ctx = egui::Context::default();
+        let output = run(&ctx, 1000.0, 600.0, |ui| {
+            app.show_list(ui, &visible, 0, false);
+        });
+
+        // Rows out of view would still be laid out, just not painted
+        let below = ctx.viewport(|viewport| {
+            (viewport.prev_pass.widgets.layers())
+                .flat_map(|(_, widgets)| widgets)
+                .filter(|widget| widget.rect.min.y > 600.0)
+                .count()
+        });
+        assert_eq!(below, 0);
+        let rows = texts(&output.shapes)
+            .into_iter()
+            .filter(|text| text.starts_with("entry "))
+            .count();
+        // Whatever fits in 600 points, give or take a row cut off at the bottom
+        let row_height = egui::Style::default().spacing.interact_size.y;
+        assert!(rows > 0);
+        assert!(
+            rows as f32 <= 600.0 / row_height + 1.0,
+            "{rows} rows laid out"
+        );
+    }
+
+    #[test]
+    fn long_entries_stay_within_the_row() {
+        let mut app = MyceliaApp {
+            entries: vec![Entry {
+                id: "long".to_owned(),
+                text: "a".repeat(100_000),
+                tags: (0..50)
+                    .map(|i| format!("{i}{}", "tag".repeat(100)))
+                    .collect(),
+                ..Entry::default()
+            }],
+            ..MyceliaApp::default()
+        };
+        let mut rects = None;
+        // About as wide as the list pane next to the view pane, or a phone
+        let output = run(&egui::Context::default(), 400.0, 600.0, |ui| {
+            let available = ui.available_rect_before_wrap();
+            let row = ui.scope(|ui| app.show_row(ui, 0, 0)).response.rect;
+            rects = Some((available, row));
+        });
+
+        let (available, row) = rects.unwrap_or_else(|| panic!("the frame wasn't laid out"));
+        assert!(row.width() > 0.0);
+        assert!(
+            row.max.x <= available.max.x,
+            "{row:?} doesn't fit in {available:?}"
+        );
+        // Some of the tags were folded away to leave room for the preview
+        let texts = texts(&output.shapes);
+        assert!(
+            texts.iter().any(|text| text.starts_with("aaa")),
+            "{texts:?}"
+        );
+        assert!(texts.iter().any(|text| text.starts_with('+')), "{texts:?}");
     }
 }
@@ -0,0 +1,41 @@
+//! Keeps the browser from acting on keys the app handles itself, on web. eframe does this
+//! for a few shortcuts, but not for these. Native has no browser to keep away.
+
+/// Whether the browser would act on `event` rather than leave it to the app. F5 would
+/// reload the page, and Ctrl+F open the browser's find bar over the editor's.
+#[cfg(target_arch = "wasm32")]
+fn is_app_key(event: &web_sys::KeyboardEvent) -> bool {
+    let command = event.ctrl_key() || event.meta_key();
+    match event.key().as_str() {
+        "F5" => true,
+        "f" | "F" => command,
+        _ => false,
+    }
+}
+
+/// Starts keeping the browser from acting on the keys of `is_app_key`, for the lifetime of
+/// the page.
+#[cfg(target_arch = "wasm32")]
+pub fn install() {
+    use eframe::wasm_bindgen::JsCast as _;
+    use eframe::wasm_bindgen::closure::Closure;
+
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let listener = Closure::<dyn Fn(web_sys::KeyboardEvent)>::new(|event| {
+        if is_app_key(&event) {
+            event.prevent_default();
+        }
+    });
+    let added =
+        window.add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+    if let Err(e) = added {
+        log::warn!("Failed to keep the browser from using the app's shortcuts: {e:?}");
+    }
+    // The listener stays for as long as the page, so it must not be dropped
+    listener.forget();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn install() {}
@@ -0,0 +1,47 @@
+//! Links to an entry on web, through the fragment of the page URL: `#entry=<id>`.
+//! Native has no URL, so there is nothing to link to.
+
+/// The id of the entry the page URL links to, if any.
+#[cfg(target_arch = "wasm32")]
+pub fn linked_entry() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let id = hash
+        .trim_start_matches('#')
+        .split('&')
+        .find_map(|param| param.strip_prefix("entry="))?;
+    let id = js_sys::decode_uri_component(id).ok()?.as_string()?;
+    (!id.is_empty()).then_some(id)
+}
+
+/// Makes the page URL link to entry `id`, or to no entry.
+///
+/// Replaces the URL rather than navigating, so viewing entries doesn't fill the history
+/// of the back button.
+#[cfg(target_arch = "wasm32")]
+pub fn link_entry(id: Option<&str>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let url = if let Some(id) = id {
+        format!("#entry={}", js_sys::encode_uri_component(id))
+    } else {
+        // An empty fragment would leave a `#` behind
+        let location = window.location();
+        let path = location.pathname().unwrap_or_default();
+        format!("{path}{}", location.search().unwrap_or_default())
+    };
+    let replaced = (window.history()).and_then(|history| {
+        history.replace_state_with_url(&eframe::wasm_bindgen::JsValue::NULL, "", Some(&url))
+    });
+    if let Err(e) = replaced {
+        log::warn!("Failed to update the URL: {e:?}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn linked_entry() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn link_entry(_id: Option<&str>) {}
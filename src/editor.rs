@@ -0,0 +1,570 @@
+use crate::app::Entry;
+use crate::find::FindBar;
+use crate::history::History;
+use crate::markdown;
+use crate::settings::SettingsComponent;
+use egui_commonmark::CommonMarkCache;
+
+/// Unsaved changes from a previous session, waiting for the user to restore or discard them.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Draft {
+    entry: Entry,
+    original: String,
+    #[serde(default)]
+    original_tags: Vec<String>,
+}
+
+/// All open editor windows, one per entry.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub(crate) struct Editors {
+    editors: Vec<EditorComponent>,
+
+    /// Persisted as well, so they are still offered if the app is closed before deciding.
+    drafts: Vec<Draft>,
+
+    /// Handed out to editors, so new entries (which have no id yet) can be told apart.
+    next_key: u64,
+}
+
+impl Editors {
+    /// Opens `entry` in a new window, unless it is already being edited.
+    pub fn edit(&mut self, entry: Entry) {
+        let open = !entry.id.is_empty() && self.editors.iter().any(|e| e.entry.id == entry.id);
+        if !open {
+            self.open(entry);
+        }
+    }
+
+    /// Opens a new entry starting out as a copy of `entry`, which saving leaves alone.
+    pub fn duplicate(&mut self, entry: &Entry) {
+        self.create(Entry {
+            text: entry.text.clone(),
+            tags: entry.tags.clone(),
+            ..Default::default()
+        });
+    }
+
+    /// Opens a new entry starting out as `entry`, which must not have an id.
+    pub fn create(&mut self, entry: Entry) {
+        self.open(entry);
+        if let Some(editor) = self.editors.last_mut() {
+            // Unsaved like anything typed into a new entry, so closing it asks first
+            editor.original.clear();
+            editor.original_tags.clear();
+        }
+    }
+
+    /// Takes a task list item ticked in the view pane, turning `unchanged` into `entry.text`,
+    /// into the entry's editor, opening one to save it from if needed. `saved` is the text
+    /// on the server. Returns false if the editor has other changes, which it would undo.
+    pub fn tick(&mut self, entry: &Entry, unchanged: &str, saved: &str) -> bool {
+        if let Some(editor) = self.editors.iter_mut().find(|e| e.entry.id == entry.id) {
+            if editor.entry.text != unchanged {
+                return false;
+            }
+            editor.entry.text.clone_from(&entry.text);
+            editor.history.checkpoint(&editor.entry.text);
+        } else {
+            self.open(entry.clone());
+            if let Some(editor) = self.editors.last_mut() {
+                saved.clone_into(&mut editor.original);
+            }
+        }
+        true
+    }
+
+    /// Whether an editor is open for the entry `id`.
+    pub fn is_editing(&self, id: &str) -> bool {
+        self.editors.iter().any(|e| e.entry.id == id)
+    }
+
+    fn open(&mut self, entry: Entry) {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.editors.push(EditorComponent::new(key, entry));
+    }
+
+    fn restore(&mut self, draft: Draft) {
+        self.open(draft.entry);
+        if let Some(editor) = self.editors.last_mut() {
+            editor.original = draft.original;
+            editor.original_tags = draft.original_tags;
+        }
+    }
+
+    /// Closes the editor `key`, e.g. once its new entry was created.
+    pub fn close(&mut self, key: u64) {
+        self.editors.retain(|e| e.key != key);
+    }
+
+    /// Closes the editor of entry `id`, e.g. once it was deleted.
+    pub fn close_entry(&mut self, id: &str) {
+        self.editors.retain(|e| e.entry.id != id);
+    }
+
+    /// Whether any editor has unsaved changes.
+    pub fn is_dirty(&self) -> bool {
+        self.editors.iter().any(EditorComponent::is_dirty)
+    }
+
+    /// Whether there are changes that weren't saved, in an editor or a draft not decided on.
+    pub fn has_unsaved(&self) -> bool {
+        self.is_dirty() || !self.drafts.is_empty()
+    }
+
+    /// Marks `entry` as saved in the editor it is open in.
+    pub fn saved(&mut self, entry: &Entry) {
+        for editor in &mut self.editors {
+            if editor.entry.id == entry.id {
+                editor.original = entry.text.clone();
+                editor.original_tags = entry.tags.clone();
+            }
+        }
+    }
+
+    /// Called after restoring from storage: unsaved changes aren't reopened straight away,
+    /// but offered as drafts in case they are stale.
+    pub fn hold_drafts(&mut self) {
+        for editor in std::mem::take(&mut self.editors) {
+            if editor.is_dirty() {
+                self.drafts.push(Draft {
+                    entry: editor.entry,
+                    original: editor.original,
+                    original_tags: editor.original_tags,
+                });
+            } else {
+                self.editors.push(editor);
+            }
+        }
+    }
+
+    /// Banner offering to restore or discard the drafts, if there are any.
+    pub(crate) fn show_draft_banner(&mut self, ui: &mut egui::Ui) {
+        if self.drafts.is_empty() {
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(match self.drafts.len() {
+                1 => "Unsaved changes from your last session were kept as a draft.".to_owned(),
+                n => format!("Unsaved changes from your last session were kept as {n} drafts."),
+            });
+            if ui.button("restore").clicked() {
+                for draft in std::mem::take(&mut self.drafts) {
+                    // Replaces a clean editor of the same entry, a dirty one keeps it pending
+                    let id = &draft.entry.id;
+                    if !id.is_empty() {
+                        self.editors.retain(|e| e.entry.id != *id || e.is_dirty());
+                    }
+                    if self
+                        .editors
+                        .iter()
+                        .any(|e| !id.is_empty() && e.entry.id == *id)
+                    {
+                        self.drafts.push(draft);
+                    } else {
+                        self.restore(draft);
+                    }
+                }
+            }
+            if ui.button("discard").clicked() {
+                self.drafts.clear();
+            }
+        });
+    }
+
+    /// Returns the key of the editor and the entry to save if a save button was clicked.
+    /// `writing` tells whether the entry of an editor, by key and id, is being saved.
+    pub(crate) fn show(
+        &mut self,
+        ctx: &egui::Context,
+        settings: &SettingsComponent,
+        writing: impl Fn(u64, &str) -> bool,
+    ) -> Option<(u64, Entry)> {
+        let mut save = None;
+        for editor in &mut self.editors {
+            let writing = writing(editor.key, &editor.entry.id);
+            if let Some(entry) = editor.show(ctx, settings, writing) {
+                save = Some((editor.key, entry));
+            }
+        }
+        self.editors.retain(|e| !e.closed);
+        save
+    }
+}
+
+/// Editor window for a single entry, with a live markdown preview.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub(crate) struct EditorComponent {
+    key: u64,
+    entry: Entry,
+    /// The text as it was when the entry was opened or last saved.
+    original: String,
+    original_tags: Vec<String>,
+    /// The tags as typed, comma-separated. Parsed into `entry.tags` on every change.
+    tags: String,
+
+    /// Show the rendered markdown next to the text box. Can be collapsed on small screens.
+    preview: bool,
+    /// Show what changed since `original` in place of the preview.
+    #[serde(skip)]
+    diff: bool,
+
+    /// Owned by the editor so the preview doesn't fight the view pane over cached layouts.
+    #[serde(skip)]
+    cache: CommonMarkCache,
+
+    #[serde(skip)]
+    history: History,
+
+    #[serde(skip)]
+    find: FindBar,
+
+    /// Set while asking whether unsaved changes may be thrown away.
+    #[serde(skip)]
+    confirm_discard: bool,
+
+    /// Set once the window was closed, it is removed at the end of the frame.
+    #[serde(skip)]
+    closed: bool,
+}
+
+impl EditorComponent {
+    fn new(key: u64, entry: Entry) -> Self {
+        let mut history = History::default();
+        history.reset(&entry.text);
+        Self {
+            key,
+            original: entry.text.clone(),
+            original_tags: entry.tags.clone(),
+            tags: entry.tags.join(", "),
+            entry,
+            history,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the text or tags differ from what was opened or last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.entry.text != self.original || self.entry.tags != self.original_tags
+    }
+
+    /// Existing entries get a window id from their id, so it is stable across sessions.
+    fn window_id(&self) -> egui::Id {
+        if self.entry.id.is_empty() {
+            egui::Id::new(("new_entry_editor", self.key))
+        } else {
+            egui::Id::new(("editor", &self.entry.id))
+        }
+    }
+
+    /// Returns the entry to save if the save button was clicked. It is disabled while
+    /// `writing` the entry.
+    fn show(
+        &mut self,
+        ctx: &egui::Context,
+        settings: &SettingsComponent,
+        writing: bool,
+    ) -> Option<Entry> {
+        let window_id = self.window_id();
+        let text_id = window_id.with("text");
+        let dirty = if self.is_dirty() { "*" } else { "" };
+        let Self {
+            entry,
+            original,
+            tags,
+            preview,
+            diff,
+            cache,
+            history,
+            find,
+            ..
+        } = self;
+
+        if !history.is_reset() {
+            // Restored from storage, start the history from here
+            history.reset(&entry.text);
+        }
+
+        // Only for the focused editor, the others keep their text
+        if ctx.memory(|m| m.has_focus(text_id)) {
+            Self::handle_shortcuts(ctx, &mut entry.text, history, find);
+        }
+
+        let mut save = None;
+        let mut open = true;
+        let mut close = false;
+
+        let title = if entry.id.is_empty() {
+            "New entry".to_owned()
+        } else {
+            format!("Edit {}", entry.id)
+        };
+        egui::Window::new(format!("{title}{dirty}"))
+            .id(window_id)
+            .open(&mut open)
+            .default_size([640.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let too_long = (settings.max_entry_len)
+                        .is_some_and(|max| entry.text.chars().count() > max);
+                    if ui
+                        .add_enabled(!too_long && !writing, egui::Button::new("save"))
+                        .on_disabled_hover_text(if writing {
+                            "Waiting for the server"
+                        } else {
+                            "Longer than the server accepts"
+                        })
+                        .clicked()
+                    {
+                        save = Some(entry.clone());
+                    }
+                    if ui.button("close").clicked() {
+                        close = true;
+                    }
+                    Self::show_history_buttons(ui, &mut entry.text, history);
+                    ui.checkbox(preview, "preview");
+                    ui.checkbox(diff, "diff")
+                        .on_hover_text("Show the changes since opening or saving");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Tags");
+                    let field = ui.add(
+                        egui::TextEdit::singleline(tags)
+                            .hint_text("comma, separated")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if field.changed() {
+                        entry.tags = parse_tags(tags);
+                    }
+                });
+
+                ui.separator();
+
+                if *preview || *diff {
+                    ui.columns(2, |ui| {
+                        let [text, rendered] = ui else {
+                            return;
+                        };
+                        Self::show_text_edit(text, text_id, entry, history, find, settings);
+                        egui::ScrollArea::vertical()
+                            .id_salt("preview")
+                            .show(rendered, |ui| {
+                                if *diff {
+                                    show_diff(ui, original, &entry.text);
+                                } else {
+                                    markdown::show(ui, cache, &entry.text, settings.markdown());
+                                }
+                            });
+                    });
+                } else {
+                    Self::show_text_edit(ui, text_id, entry, history, find, settings);
+                }
+            });
+
+        if close || !open {
+            if self.is_dirty() {
+                self.confirm_discard = true;
+            } else {
+                self.closed = true;
+            }
+        }
+
+        self.show_discard_confirmation(ctx);
+
+        save
+    }
+
+    fn show_history_buttons(ui: &mut egui::Ui, text: &mut String, history: &mut History) {
+        if ui
+            .add_enabled(history.can_undo(), egui::Button::new("undo"))
+            .on_hover_text("Ctrl+Z")
+            .clicked()
+        {
+            history.undo(text);
+        }
+        if ui
+            .add_enabled(history.can_redo(), egui::Button::new("redo"))
+            .on_hover_text("Ctrl+Y")
+            .clicked()
+        {
+            history.redo(text);
+        }
+    }
+
+    /// Consumes the shortcuts before the text box sees them, so its own undo doesn't run as well.
+    fn handle_shortcuts(
+        ctx: &egui::Context,
+        text: &mut String,
+        history: &mut History,
+        find: &mut FindBar,
+    ) {
+        ctx.input_mut(|i| {
+            let find_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::F);
+            if i.consume_shortcut(&find_shortcut) {
+                find.toggle();
+            }
+            let redo_shift = egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            );
+            let redo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+            let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+            if i.consume_shortcut(&redo_shift) || i.consume_shortcut(&redo) {
+                history.redo(text);
+            } else if i.consume_shortcut(&undo) {
+                history.undo(text);
+            }
+        });
+    }
+
+    fn show_discard_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.confirm_discard {
+            return;
+        }
+
+        egui::Window::new("Discard changes?")
+            .id(self.window_id().with("discard"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The entry has unsaved changes.");
+                ui.horizontal(|ui| {
+                    if ui.button("Discard").clicked() {
+                        self.closed = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_discard = false;
+                    }
+                });
+            });
+    }
+
+    fn show_text_edit(
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        entry: &mut Entry,
+        history: &mut History,
+        find: &mut FindBar,
+        settings: &SettingsComponent,
+    ) {
+        find.show(ui, &mut entry.text, history);
+
+        let font = if settings.editor_monospace {
+            egui::TextStyle::Monospace.into()
+        } else {
+            egui::FontSelection::Default
+        };
+        let font_id = font.clone().resolve(ui.style());
+
+        if let Some(selection) = find.take_selection(&entry.text) {
+            let mut state = egui::text_edit::TextEditState::load(ui.ctx(), id).unwrap_or_default();
+            state
+                .cursor
+                .set_char_range(Some(egui::text::CCursorRange::two(
+                    egui::text::CCursor::new(selection.start),
+                    egui::text::CCursor::new(selection.end),
+                )));
+            state.store(ui.ctx(), id);
+        }
+
+        let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+            let job = find.highlight(ui, text.as_str(), font_id.clone(), wrap_width);
+            ui.fonts_mut(|f| f.layout_job(job))
+        };
+        egui::ScrollArea::vertical().id_salt("text").show(ui, |ui| {
+            let mut text_edit = egui::TextEdit::multiline(&mut entry.text)
+                .id(id)
+                .font(font)
+                .desired_width(f32::INFINITY)
+                .desired_rows(20);
+            if find.is_open() {
+                text_edit = text_edit.layouter(&mut layouter);
+            }
+            let response = ui.add(text_edit);
+            if response.changed() {
+                history.changed(&entry.text);
+            }
+        });
+
+        // Cheap enough to redo every frame for the size of a note
+        let text = &entry.text;
+        let chars = text.chars().count();
+        let count = format!(
+            "{chars} characters, {} words",
+            text.split_whitespace().count()
+        );
+        match settings.max_entry_len {
+            Some(max) if chars > max => {
+                let over = format!("{count}, {} over the limit of {max}", chars - max);
+                ui.colored_label(ui.visuals().error_fg_color, over);
+            }
+            Some(max) if chars * 10 >= max * 9 => {
+                ui.colored_label(ui.visuals().warn_fg_color, format!("{count} of {max}"));
+            }
+            _ => {
+                ui.weak(count);
+            }
+        }
+    }
+}
+
+/// Splits `tags` at commas, dropping empty and repeated tags.
+fn parse_tags(tags: &str) -> Vec<String> {
+    let mut parsed: Vec<String> = vec![];
+    for tag in tags.split(',').map(str::trim) {
+        if !tag.is_empty() && !parsed.iter().any(|t| t == tag) {
+            parsed.push(tag.to_owned());
+        }
+    }
+    parsed
+}
+
+impl Default for EditorComponent {
+    fn default() -> Self {
+        Self {
+            key: 0,
+            entry: Entry::default(),
+            original: String::new(),
+            original_tags: vec![],
+            tags: String::new(),
+            preview: true,
+            diff: false,
+            cache: CommonMarkCache::default(),
+            history: History::default(),
+            find: FindBar::default(),
+            confirm_discard: false,
+            closed: false,
+        }
+    }
+}
+
+/// The lines of `text` against those of `original`: removed ones in red, added ones in green.
+fn show_diff(ui: &mut egui::Ui, original: &str, text: &str) {
+    let diff = similar::TextDiff::from_lines(original, text);
+    if diff.ratio() >= 1.0 {
+        ui.weak("No changes");
+        return;
+    }
+
+    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
+    let mut job = egui::text::LayoutJob::default();
+    for change in diff.iter_all_changes() {
+        let (sign, color) = match change.tag() {
+            similar::ChangeTag::Delete => ("-", egui::Color32::RED),
+            similar::ChangeTag::Insert => ("+", egui::Color32::GREEN),
+            similar::ChangeTag::Equal => (" ", ui.visuals().weak_text_color()),
+        };
+        let format = egui::TextFormat::simple(font_id.clone(), color);
+        job.append(&format!("{sign} {}", change.value()), 0.0, format);
+        if change.missing_newline() {
+            job.append("\n", 0.0, egui::TextFormat::default());
+        }
+    }
+    job.wrap.max_width = ui.available_width();
+    ui.label(job);
+}
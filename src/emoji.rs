@@ -0,0 +1,149 @@
+//! `:name:` shortcodes, as GitHub and chat apps understand them, turned into emoji.
+
+use std::borrow::Cow;
+
+/// The common shortcodes, sorted by name for binary search. Only emoji the default fonts
+/// can show are included, the others would render as a box.
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("blush", "😊"),
+    ("books", "📚"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("clap", "👏"),
+    ("coffee", "☕"),
+    ("confetti_ball", "🎊"),
+    ("crescent_moon", "🌙"),
+    ("cry", "😢"),
+    ("dart", "🎯"),
+    ("date", "📅"),
+    ("earth_africa", "🌍"),
+    ("exclamation", "❗"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer_and_wrench", "🛠"),
+    ("heart", "❤"),
+    ("heart_eyes", "😍"),
+    ("heavy_check_mark", "✔"),
+    ("house", "🏠"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("kissing_heart", "😘"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("memo", "📝"),
+    ("moneybag", "💰"),
+    ("muscle", "💪"),
+    ("mushroom", "🍄"),
+    ("neutral_face", "😐"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("paperclip", "📎"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rage", "😡"),
+    ("rocket", "🚀"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("seedling", "🌱"),
+    ("sleeping", "😴"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("speech_balloon", "💬"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("warning", "⚠"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+fn lookup(name: &str) -> Option<&'static str> {
+    let index = SHORTCODES
+        .binary_search_by_key(&name, |&(name, _)| name)
+        .ok()?;
+    SHORTCODES.get(index).map(|&(_, emoji)| emoji)
+}
+
+/// `text` with the known shortcodes replaced by their emoji. Shortcodes in code blocks and
+/// code spans are left alone, as are unknown ones.
+pub fn expand(text: &str) -> Cow<'_, str> {
+    if !text.contains(':') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut in_code = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        in_code ^= fence;
+        if fence || in_code {
+            expanded.push_str(line);
+            continue;
+        }
+        // Every other part is inside a code span
+        for (index, part) in line.split('`').enumerate() {
+            if index > 0 {
+                expanded.push('`');
+            }
+            if index % 2 == 0 {
+                changed |= expand_part(part, &mut expanded);
+            } else {
+                expanded.push_str(part);
+            }
+        }
+    }
+    if changed {
+        Cow::Owned(expanded)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Appends `text` to `expanded`, with its shortcodes replaced. Returns whether there were any.
+fn expand_part(text: &str, expanded: &mut String) -> bool {
+    let mut changed = false;
+    let mut rest = text;
+    while let Some(colon) = rest.find(':') {
+        let (before, after) = rest.split_at(colon);
+        expanded.push_str(before);
+        let candidate = after.get(1..).unwrap_or_default();
+        let len = candidate
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')))
+            .unwrap_or(candidate.len());
+        let (name, after_name) = candidate.split_at(len);
+        if let Some((after_code, emoji)) = after_name.strip_prefix(':').zip(lookup(name)) {
+            expanded.push_str(emoji);
+            rest = after_code;
+            changed = true;
+        } else {
+            // Not a known one, but what looked like its closing colon may open one
+            expanded.push(':');
+            rest = candidate;
+        }
+    }
+    expanded.push_str(rest);
+    changed
+}
@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+/// How many errors are kept, older ones are dropped.
+const CAPACITY: usize = 100;
+
+struct LoggedError {
+    time: chrono::DateTime<chrono::Local>,
+    text: String,
+}
+
+/// The recent errors, kept after the next action replaced them, for failures that come
+/// and go. Shown in a panel above the status bar.
+#[derive(Default)]
+pub(crate) struct ErrorLog {
+    errors: VecDeque<LoggedError>,
+    open: bool,
+}
+
+impl ErrorLog {
+    /// An error repeating the last one only updates its time, so that it can't push the
+    /// others out.
+    pub fn push(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if let Some(last) = self.errors.back_mut()
+            && last.text == text
+        {
+            last.time = chrono::Local::now();
+            return;
+        }
+        if self.errors.len() >= CAPACITY {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(LoggedError {
+            time: chrono::Local::now(),
+            text,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+
+        egui::TopBottomPanel::bottom("error_log")
+            .resizable(true)
+            .default_height(120.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Errors");
+                    if ui
+                        .add_enabled(!self.errors.is_empty(), egui::Button::new("clear"))
+                        .clicked()
+                    {
+                        self.errors.clear();
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        if self.errors.is_empty() {
+                            ui.weak("No errors so far");
+                        }
+                        for error in &self.errors {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.weak(error.time.format("%H:%M:%S").to_string());
+                                ui.colored_label(ui.visuals().error_fg_color, &error.text);
+                            });
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,133 @@
+//! Saving and opening files, on native through file dialogs and on web as a download and
+//! an `<input type=file>`.
+
+/// Lets the user save `contents` to a file, suggesting `name`.
+///
+/// Cancelling the dialog is not an error.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_file(name: &str, contents: &str) -> Result<(), String> {
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(name)
+        .add_filter("JSON", &["json"])
+        .save_file()
+    else {
+        return Ok(());
+    };
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Lets the user pick a file and hands its contents to `on_done`.
+///
+/// `on_done` is not called if the user cancels.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn open_file(on_done: impl 'static + FnOnce(Result<String, String>)) {
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("JSON", &["json"])
+        .pick_file()
+    else {
+        return;
+    };
+    on_done(
+        std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display())),
+    );
+}
+
+/// Lets the user pick a file and hands its contents to `on_done`, once the browser read it.
+///
+/// `on_done` is not called if the user cancels.
+#[cfg(target_arch = "wasm32")]
+pub fn open_file(on_done: impl 'static + FnOnce(Result<String, String>)) {
+    use eframe::wasm_bindgen::{JsCast as _, closure::Closure};
+
+    let input = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("input").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok());
+    let Some(input) = input else {
+        on_done(Err("Failed to create a file input".to_owned()));
+        return;
+    };
+    input.set_type("file");
+    input.set_accept(".json,application/json");
+
+    let file_input = input.clone();
+    let on_change = Closure::once(move |_: web_sys::Event| {
+        let Some(file) = file_input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            let text = wasm_bindgen_futures::JsFuture::from(file.text()).await;
+            on_done(match text {
+                Ok(text) => text
+                    .as_string()
+                    .ok_or_else(|| "File is not text".to_owned()),
+                Err(e) => Err(format!("Failed to read {}: {e:?}", file.name())),
+            });
+        });
+    });
+    input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+    // The browser owns the input from here on, so the handler has to outlive this call
+    on_change.forget();
+    input.click();
+}
+
+/// Offers `contents` as a download called `name`.
+#[cfg(target_arch = "wasm32")]
+pub fn save_file(name: &str, contents: &str) -> Result<(), String> {
+    use eframe::wasm_bindgen::closure::Closure;
+    use eframe::wasm_bindgen::{JsCast as _, JsValue};
+
+    /// How long the browser gets to start the download before its URL goes away.
+    const REVOKE_DELAY_MS: i32 = 10_000;
+
+    let js_error = |e: JsValue| format!("Failed to download {name}: {e:?}");
+
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/json");
+    let blob =
+        web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("No document")?;
+    let anchor = document
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|element| js_error(element.into()))?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+
+    // Revoking right away can cancel the download in some browsers
+    let revoke = Closure::once_into_js(move || {
+        if let Err(e) = web_sys::Url::revoke_object_url(&url) {
+            log::warn!("Failed to revoke the download URL: {e:?}");
+        }
+    });
+    let window = web_sys::window().ok_or("No window")?;
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(
+            revoke.unchecked_ref(),
+            REVOKE_DELAY_MS,
+        )
+        .map_err(js_error)?;
+    Ok(())
+}
+
+/// The text of a file dropped onto the window. On web it comes with its contents, on
+/// native only with its path.
+pub fn read_dropped(file: &egui::DroppedFile) -> Result<String, String> {
+    let name = (file.path.as_ref()).map_or_else(|| file.name.clone(), |p| p.display().to_string());
+    let bytes = match (&file.bytes, &file.path) {
+        (Some(bytes), _) => bytes.to_vec(),
+        (None, Some(path)) => {
+            std::fs::read(path).map_err(|e| format!("Failed to read {name}: {e}"))?
+        }
+        (None, None) => return Err(format!("Nothing was dropped of {name}")),
+    };
+    String::from_utf8(bytes).map_err(|e| format!("{name} isn't UTF-8 text: {e}"))
+}
@@ -0,0 +1,241 @@
+use crate::history::History;
+use std::ops::Range;
+
+/// Find/replace bar of an editor window, toggled with Ctrl+F.
+///
+/// `TextEdit` has no search of its own, so the buffer is searched here and matches are
+/// highlighted through a custom layouter.
+#[derive(Default)]
+pub(crate) struct FindBar {
+    open: bool,
+    query: String,
+    replacement: String,
+    case_sensitive: bool,
+    /// Index of the match that navigation and "replace" act on.
+    current: usize,
+    /// Set after navigating, so the text box selects the current match once.
+    select: bool,
+    /// Set when opened, so typing goes to the query.
+    focus_query: bool,
+}
+
+/// Byte ranges of all non-overlapping occurrences of `query` in `text`.
+pub fn find_all(text: &str, query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return vec![];
+    }
+    let mut matches = vec![];
+    let mut start = 0;
+    while let Some(rest) = text.get(start..) {
+        let Some(next) = rest.chars().next() else {
+            break;
+        };
+        if let Some(len) = match_len(rest, query, case_sensitive) {
+            matches.push(start..start + len);
+            start += len;
+        } else {
+            start += next.len_utf8();
+        }
+    }
+    matches
+}
+
+/// Length in bytes of the match of `query` at the start of `text`, if there is one.
+fn match_len(text: &str, query: &str, case_sensitive: bool) -> Option<usize> {
+    let mut len = 0;
+    let mut chars = text.chars();
+    for q in query.chars() {
+        let c = chars.next()?;
+        let same = if case_sensitive {
+            c == q
+        } else {
+            c.to_lowercase().eq(q.to_lowercase())
+        };
+        if !same {
+            return None;
+        }
+        len += c.len_utf8();
+    }
+    Some(len)
+}
+
+/// `text` as a single line of body text, with the byte `ranges` highlighted. They are
+/// sorted by start, and may overlap.
+pub fn highlight_matches(
+    ui: &egui::Ui,
+    text: &str,
+    ranges: Vec<Range<usize>>,
+) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let format = egui::TextFormat::simple(font_id, ui.visuals().text_color());
+    let highlight = egui::TextFormat {
+        background: ui.visuals().selection.bg_fill.gamma_multiply(0.4),
+        ..format.clone()
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    let mut end = 0;
+    for range in ranges {
+        // Where ranges overlap, only the rest is left to highlight
+        let range = range.start.max(end)..range.end;
+        if range.is_empty() {
+            continue;
+        }
+        job.append(
+            text.get(end..range.start).unwrap_or_default(),
+            0.0,
+            format.clone(),
+        );
+        job.append(
+            text.get(range.clone()).unwrap_or_default(),
+            0.0,
+            highlight.clone(),
+        );
+        end = range.end;
+    }
+    job.append(text.get(end..).unwrap_or_default(), 0.0, format);
+    job
+}
+
+impl FindBar {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.select = self.open;
+        self.focus_query = self.open;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Byte ranges of all non-overlapping matches in `text`, none while the bar is closed.
+    pub fn matches(&self, text: &str) -> Vec<Range<usize>> {
+        if self.open {
+            find_all(text, &self.query, self.case_sensitive)
+        } else {
+            vec![]
+        }
+    }
+
+    /// The current match, once after navigating to it, as char range for the text box.
+    pub fn take_selection(&mut self, text: &str) -> Option<Range<usize>> {
+        if !std::mem::take(&mut self.select) {
+            return None;
+        }
+        let range = self.matches(text).get(self.current)?.clone();
+        let chars = |end: usize| text.get(..end).map_or(0, |t| t.chars().count());
+        Some(chars(range.start)..chars(range.end))
+    }
+
+    /// `text` laid out like the text box does, with the matches highlighted.
+    pub fn highlight(
+        &self,
+        ui: &egui::Ui,
+        text: &str,
+        font_id: egui::FontId,
+        wrap_width: f32,
+    ) -> egui::text::LayoutJob {
+        let color = ui.visuals().widgets.inactive.text_color();
+        let format = egui::TextFormat::simple(font_id, color);
+        let highlight = |current: bool| egui::TextFormat {
+            background: if current {
+                ui.visuals().selection.bg_fill
+            } else {
+                ui.visuals().selection.bg_fill.gamma_multiply(0.4)
+            },
+            ..format.clone()
+        };
+
+        let mut job = egui::text::LayoutJob::default();
+        let mut end = 0;
+        for (index, range) in self.matches(text).into_iter().enumerate() {
+            let before = text.get(end..range.start).unwrap_or_default();
+            job.append(before, 0.0, format.clone());
+            let matched = text.get(range.clone()).unwrap_or_default();
+            job.append(matched, 0.0, highlight(index == self.current));
+            end = range.end;
+        }
+        job.append(text.get(end..).unwrap_or_default(), 0.0, format);
+        job.wrap.max_width = wrap_width;
+        job
+    }
+
+    pub(crate) fn show(&mut self, ui: &mut egui::Ui, text: &mut String, history: &mut History) {
+        if !self.open {
+            return;
+        }
+
+        let matches = self.matches(text);
+        self.current = self.current.min(matches.len().saturating_sub(1));
+
+        ui.horizontal(|ui| {
+            let find = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Find")
+                    .desired_width(160.0),
+            );
+            if std::mem::take(&mut self.focus_query) {
+                find.request_focus();
+            }
+            if find.changed() {
+                self.current = 0;
+            }
+            if find.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.open = false;
+            }
+            // Enter in the field goes to the next match, like in a browser
+            let enter = find.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if enter {
+                find.request_focus();
+            }
+            ui.checkbox(&mut self.case_sensitive, "Aa")
+                .on_hover_text("Case sensitive");
+
+            ui.label(match matches.len() {
+                0 => "no matches".to_owned(),
+                n => format!("{} of {n}", self.current + 1),
+            });
+            let any = !matches.is_empty();
+            if ui
+                .add_enabled(any, egui::Button::new("⏶"))
+                .on_hover_text("Previous")
+                .clicked()
+            {
+                self.current = self.current.checked_sub(1).unwrap_or(matches.len() - 1);
+                self.select = true;
+            }
+            if ui
+                .add_enabled(any, egui::Button::new("⏷"))
+                .on_hover_text("Next")
+                .clicked()
+                || (enter && any)
+            {
+                self.current = (self.current + 1) % matches.len();
+                self.select = true;
+            }
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.replacement)
+                    .hint_text("Replace with")
+                    .desired_width(160.0),
+            );
+            if ui.add_enabled(any, egui::Button::new("replace")).clicked() {
+                if let Some(range) = matches.get(self.current) {
+                    text.replace_range(range.clone(), &self.replacement);
+                    history.checkpoint(text);
+                    self.select = true;
+                }
+            }
+            if ui
+                .add_enabled(any, egui::Button::new("replace all"))
+                .clicked()
+            {
+                // Back to front, so the earlier ranges stay valid
+                for range in matches.iter().rev() {
+                    text.replace_range(range.clone(), &self.replacement);
+                }
+                history.checkpoint(text);
+            }
+        });
+    }
+}
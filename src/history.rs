@@ -0,0 +1,89 @@
+use web_time::{Duration, Instant};
+
+/// Most snapshots kept, the oldest are dropped beyond this.
+const MAX_DEPTH: usize = 100;
+
+/// Edits closer together than this are undone as one, so undo doesn't go letter by letter.
+const BURST: Duration = Duration::from_secs(1);
+
+/// Undo/redo snapshots of a text buffer.
+///
+/// Unlike the undoer built into `egui::TextEdit` this survives the widget losing focus,
+/// and can track changes made to the text from outside the widget.
+#[derive(Default)]
+pub(crate) struct History {
+    undo: Vec<String>,
+    redo: Vec<String>,
+    /// The text after the last recorded change, or `None` before the first `reset`.
+    settled: Option<String>,
+    last_edit: Option<Instant>,
+}
+
+impl History {
+    /// Forgets all snapshots, `text` is the new starting point.
+    pub fn reset(&mut self, text: &str) {
+        self.undo.clear();
+        self.redo.clear();
+        self.settled = Some(text.to_owned());
+        self.last_edit = None;
+    }
+
+    pub fn is_reset(&self) -> bool {
+        self.settled.is_some()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Records that the text was changed to `text`.
+    pub fn changed(&mut self, text: &str) {
+        let new_burst = self.last_edit.is_none_or(|t| t.elapsed() > BURST);
+        if new_burst {
+            if let Some(settled) = self.settled.take() {
+                self.push_undo(settled);
+            }
+            self.redo.clear();
+        }
+        self.settled = Some(text.to_owned());
+        self.last_edit = Some(Instant::now());
+    }
+
+    /// Records a change that is undone on its own, e.g. a replace-all, rather than merged
+    /// with the typing around it.
+    pub fn checkpoint(&mut self, text: &str) {
+        self.last_edit = None;
+        self.changed(text);
+        self.last_edit = None;
+    }
+
+    pub fn undo(&mut self, text: &mut String) {
+        if let Some(previous) = self.undo.pop() {
+            self.redo.push(std::mem::replace(text, previous));
+            self.settle(text);
+        }
+    }
+
+    pub fn redo(&mut self, text: &mut String) {
+        if let Some(next) = self.redo.pop() {
+            self.push_undo(std::mem::replace(text, next));
+            self.settle(text);
+        }
+    }
+
+    fn push_undo(&mut self, text: String) {
+        self.undo.push(text);
+        if self.undo.len() > MAX_DEPTH {
+            self.undo.remove(0);
+        }
+    }
+
+    fn settle(&mut self, text: &str) {
+        self.settled = Some(text.to_owned());
+        self.last_edit = None;
+    }
+}
@@ -1,4 +1,19 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod browser_keys;
+mod deep_link;
+mod editor;
+mod emoji;
+mod error_log;
+mod file_io;
+mod find;
+mod history;
+mod markdown;
+mod mock;
+mod notifications;
+mod palette;
+mod request_id;
+mod search;
+mod settings;
 pub use app::MyceliaApp;
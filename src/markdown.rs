@@ -0,0 +1,353 @@
+//! Markdown rendering shared by the view pane and the editor preview.
+
+use crate::emoji;
+use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use std::borrow::Cow;
+
+/// How entries are rendered, from the settings.
+#[derive(Clone, Copy)]
+pub struct Options {
+    /// See `viewer`.
+    pub render_html: bool,
+    /// Show `:tada:` style shortcodes as emoji. The text itself keeps them.
+    pub emoji: bool,
+}
+
+/// A viewer configured the same way everywhere entries are rendered.
+///
+/// Fenced code blocks are highlighted with syntect, which picks the light or dark theme
+/// from the current visuals, so it follows the app's theme setting.
+///
+/// HTML is shown as written unless `render_html` is set. Setting it trusts whoever wrote
+/// the entries: the markup is interpreted instead of shown, so it can hide or disguise
+/// text. egui can't render HTML itself, so HTML blocks are reduced to their text content;
+/// inline HTML is always shown as written.
+///
+/// Images are fetched by the loaders installed in `MyceliaApp::new` and scaled down to the
+/// width of the pane. One that can't be loaded shows as a ⚠, with the reason on hover.
+fn viewer(render_html: bool) -> CommonMarkViewer<'static> {
+    let viewer = CommonMarkViewer::new()
+        .syntax_theme_dark("base16-ocean.dark")
+        .syntax_theme_light("base16-ocean.light");
+    if render_html {
+        viewer.render_html_fn(Some(&show_html_block))
+    } else {
+        viewer
+    }
+}
+
+fn show_html_block(ui: &mut egui::Ui, html: &str) {
+    ui.label(html_to_text(html).trim());
+}
+
+/// The text content of `html`, with block-level tags turned into line breaks.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    // Name of the element whose content isn't text, e.g. `script`, until it is closed
+    let mut skipping: Option<String> = None;
+
+    while let Some(start) = rest.find('<') {
+        let (before, tag) = rest.split_at(start);
+        if skipping.is_none() {
+            text.push_str(before);
+        }
+        let Some(end) = tag.find('>') else {
+            rest = tag;
+            break;
+        };
+        let (tag, after) = tag.split_at(end + 1);
+        rest = after;
+
+        let closing = tag.starts_with("</");
+        let name = tag
+            .trim_start_matches(['<', '/'])
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if let Some(skipped) = &skipping {
+            if closing && name == *skipped {
+                skipping = None;
+            }
+            continue;
+        }
+        match name.as_str() {
+            "script" | "style" | "head" | "title" if !closing => skipping = Some(name),
+            "br" | "p" | "div" | "li" | "tr" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                text.push('\n');
+            }
+            _ => {}
+        }
+    }
+    if skipping.is_none() {
+        text.push_str(rest);
+    }
+
+    // `&amp;` last, so `&amp;lt;` stays `&lt;`
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Renders `text` and makes sure clicked links can't navigate away from the app.
+///
+/// The viewer emits an `OpenUrl` for every clicked link, which on web would replace the
+/// app with the linked page. Those are rewritten to open in a new tab, and anything that
+/// isn't http(s) (`javascript:`, `file:`, in-page `#anchors`, ...) is dropped. The viewer
+/// doesn't expose where headings end up, so anchors can't be scrolled to.
+///
+/// Front matter isn't shown, see `split_front_matter`.
+pub fn show(ui: &mut egui::Ui, cache: &mut CommonMarkCache, text: &str, options: Options) {
+    let before = ui.ctx().output(|o| o.commands.len());
+    let text = if options.emoji {
+        emoji::expand(body(text))
+    } else {
+        Cow::Borrowed(body(text))
+    };
+    viewer(options.render_html).show(ui, cache, &text);
+    keep_links_in_app(ui, before);
+}
+
+/// Like `show`, but task list items (`- [ ] ...`) can be ticked, which edits `text`.
+/// Returns whether it did.
+pub fn show_mut(
+    ui: &mut egui::Ui,
+    cache: &mut CommonMarkCache,
+    text: &mut String,
+    options: Options,
+) -> bool {
+    let before = ui.ctx().output(|o| o.commands.len());
+    let mut body = text.split_off(text.len() - self::body(text).len());
+    let unchanged = body.clone();
+    let expanded = match options.emoji.then(|| emoji::expand(&body)) {
+        Some(Cow::Owned(expanded)) => Some(expanded),
+        _ => None,
+    };
+    if let Some(mut expanded) = expanded {
+        viewer(options.render_html).show_mut(ui, cache, &mut expanded);
+        // Expanding doesn't add or remove any task items, so they can be matched up
+        for ((start, checked), (_, ticked)) in task_items(&unchanged).zip(task_items(&expanded)) {
+            if checked != ticked {
+                body.replace_range(start..start + 3, if ticked { "[x]" } else { "[ ]" });
+            }
+        }
+    } else {
+        viewer(options.render_html).show_mut(ui, cache, &mut body);
+    }
+    keep_links_in_app(ui, before);
+    let changed = body != unchanged;
+    text.push_str(&body);
+    changed
+}
+
+/// Where the `[ ]` of each task list item of `text` is, and whether it is checked. Items
+/// nested in block quotes aren't recognized.
+fn task_items(text: &str) -> impl Iterator<Item = (usize, bool)> {
+    let mut start = 0;
+    text.split_inclusive('\n').filter_map(move |line| {
+        let line_start = start;
+        start += line.len();
+        let item = line.trim_start();
+        let numbered = item.trim_start_matches(|c: char| c.is_ascii_digit());
+        let rest = match item.strip_prefix(['-', '*', '+']) {
+            Some(rest) => rest,
+            None if numbered.len() < item.len() => numbered.strip_prefix(['.', ')'])?,
+            None => return None,
+        };
+        let marker = rest.trim_start_matches([' ', '\t']);
+        if marker.len() == rest.len() {
+            return None;
+        }
+        let checked = match marker.get(..3)? {
+            "[ ]" => false,
+            "[x]" | "[X]" => true,
+            _ => return None,
+        };
+        Some((line_start + line.len() - marker.len(), checked))
+    })
+}
+
+/// Rewrites the `OpenUrl` commands emitted after the first `before`, see `show`.
+fn keep_links_in_app(ui: &egui::Ui, before: usize) {
+    ui.ctx().output_mut(|o| {
+        let mut index = 0;
+        o.commands.retain_mut(|command| {
+            index += 1;
+            match command {
+                egui::OutputCommand::OpenUrl(open) if index > before => {
+                    open.new_tab = true;
+                    is_web_link(&open.url)
+                }
+                _ => true,
+            }
+        });
+    });
+}
+
+fn is_web_link(url: &str) -> bool {
+    let url = url.trim_start();
+    ["http://", "https://"].iter().any(|scheme| {
+        url.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// Stops images from being fetched from other servers, by failing them before the
+/// `egui_extras` HTTP loader gets to them. egui asks the loader added last first.
+struct BlockRemoteImages;
+
+impl BlockRemoteImages {
+    const ID: &'static str = egui::generate_loader_id!(BlockRemoteImages);
+}
+
+impl egui::load::BytesLoader for BlockRemoteImages {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn load(&self, _ctx: &egui::Context, uri: &str) -> egui::load::BytesLoadResult {
+        if is_web_link(uri) {
+            Err(egui::load::LoadError::Loading(
+                "Remote images are turned off in the settings".to_owned(),
+            ))
+        } else {
+            Err(egui::load::LoadError::NotSupported)
+        }
+    }
+
+    fn forget(&self, _uri: &str) {}
+
+    fn forget_all(&self) {}
+
+    fn byte_size(&self) -> usize {
+        0
+    }
+}
+
+/// Lets images in entries be fetched over http(s), or not.
+pub fn allow_remote_images(ctx: &egui::Context, allow: bool) {
+    if allow {
+        ctx.loaders()
+            .bytes
+            .lock()
+            .retain(|loader| loader.id() != BlockRemoteImages::ID);
+    } else if !ctx.is_loader_installed(BlockRemoteImages::ID) {
+        ctx.add_bytes_loader(std::sync::Arc::new(BlockRemoteImages));
+    }
+    // Otherwise images loaded (or refused) before stay that way
+    ctx.forget_all_images();
+}
+
+/// Splits YAML front matter off the start of `text`: the lines between a first line of
+/// `---` and the next line of `---` or `...`. Returns the front matter and the rest.
+///
+/// Only taken for front matter if it starts with a `key:`, so an entry starting with a
+/// horizontal rule doesn't lose everything up to the next one.
+pub fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("---")?;
+    let rest = (rest.strip_prefix("\r\n")).or_else(|| rest.strip_prefix('\n'))?;
+    let key = rest.lines().next()?.split_once(':')?.0;
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return None;
+    }
+    let mut end = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some((rest.get(..end)?, rest.get(end + line.len()..)?));
+        }
+        end += line.len();
+    }
+    None
+}
+
+/// `text` without its front matter, if any.
+pub fn body(text: &str) -> &str {
+    split_front_matter(text).map_or(text, |(_, body)| body)
+}
+
+/// The `title` set in the front matter of `text`, without quotes.
+pub fn title(text: &str) -> Option<String> {
+    let (front_matter, _) = split_front_matter(text)?;
+    front_matter.lines().find_map(|line| {
+        let value = line.strip_prefix("title:")?.trim();
+        let unquoted = ['"', '\'']
+            .iter()
+            .find_map(|&quote| value.strip_prefix(quote)?.strip_suffix(quote));
+        let title = unquoted.unwrap_or(value).trim();
+        (!title.is_empty()).then(|| title.to_owned())
+    })
+}
+
+/// An ATX heading (`#` to `######`) of a markdown document.
+pub struct Heading {
+    pub level: usize,
+    pub title: String,
+    /// Byte offset of the line the heading is on.
+    pub start: usize,
+}
+
+/// The headings of `text`, for a table of contents. Setext headings (underlined with `=`
+/// or `-`) aren't recognized, and neither is anything inside a fenced code block or the
+/// front matter.
+pub fn headings(text: &str) -> Vec<Heading> {
+    let mut headings = vec![];
+    let mut in_code = false;
+    let body = body(text);
+    let mut start = text.len() - body.len();
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        // More than three spaces of indentation make it a code block
+        let indented = line.len() - trimmed.len() > 3;
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        } else if !in_code && !indented {
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            let rest = trimmed.get(level..).unwrap_or_default();
+            if (1..=6).contains(&level) && (rest.trim().is_empty() || rest.starts_with([' ', '\t']))
+            {
+                let title = rest.trim().trim_end_matches('#').trim_end();
+                headings.push(Heading {
+                    level,
+                    title: title.to_owned(),
+                    start,
+                });
+            }
+        }
+        start += line.len();
+    }
+    headings
+}
+
+/// Scrolls the surrounding scroll area to `heading` of `text`, which is about to be shown
+/// at the cursor.
+///
+/// The viewer doesn't expose where it put the headings, so the text before the heading is
+/// laid out on its own, invisibly, to learn how far down it starts. Rendering the text in
+/// sections instead would break reference-style links and footnotes across them.
+pub fn scroll_to_heading(
+    ui: &mut egui::Ui,
+    cache: &mut CommonMarkCache,
+    text: &str,
+    options: Options,
+    heading: &Heading,
+) {
+    let top = ui.cursor().top();
+    let before = text.get(..heading.start).unwrap_or_default();
+    // A child that takes no space of `ui`
+    let mut measure = ui.new_child(egui::UiBuilder::new().invisible().sizing_pass());
+    show(&mut measure, cache, before, options);
+    let offset = if body(before).trim().is_empty() {
+        0.0
+    } else {
+        measure.min_rect().height() + ui.spacing().item_spacing.y
+    };
+    let rect = egui::Rect::from_min_size(
+        egui::pos2(ui.max_rect().left(), top + offset),
+        egui::vec2(ui.available_width(), 1.0),
+    );
+    ui.scroll_to_rect(rect, Some(egui::Align::TOP));
+}
@@ -0,0 +1,64 @@
+//! Canned data for working on the UI without a server.
+
+use crate::app::{Entry, Timestamp};
+
+/// What fetching the entries returns in mock mode.
+pub fn mock_entries() -> Vec<Entry> {
+    // Created an hour apart, the first one most recently
+    let now = chrono::Utc::now().timestamp();
+    let entry = |id: &str, text: &str, tags: &[&str]| Entry {
+        id: id.to_owned(),
+        text: text.to_owned(),
+        tags: tags.iter().map(|&tag| tag.to_owned()).collect(),
+        created_at: id
+            .parse::<i64>()
+            .ok()
+            .map(|n| Timestamp::Epoch(now - n * 3600)),
+        ..Entry::default()
+    };
+    vec![
+        entry(
+            "1",
+            "# Welcome\n\nThis is **mock mode**, nothing here comes from the server.",
+            &["mock"],
+        ),
+        entry(
+            "2",
+            "Shopping list\n\n- [ ] mushrooms\n- [x] rice\n- [ ] miso",
+            &["list"],
+        ),
+        entry(
+            "3",
+            "Snippet\n\n```rust\nfn main() {\n    println!(\"hello mycelia\");\n}\n```",
+            &["code", "rust"],
+        ),
+        entry(
+            "4",
+            "A link to [the egui docs](https://docs.rs/egui) and a table:\n\n\
+             | a | b |\n|---|---|\n| 1 | 2 |",
+            &[],
+        ),
+        entry(
+            "5",
+            &"A very long line that gets truncated. ".repeat(20),
+            &["long"],
+        ),
+        // Neither the text nor the tags may push the row's buttons out of view
+        entry(
+            "6",
+            &"unbreakable".repeat(40),
+            &[
+                "long",
+                "pathological",
+                "a-tag-that-is-way-too-long-to-show",
+                "more",
+                "tags",
+            ],
+        ),
+        entry(
+            "7",
+            "---\ntitle: \"Listed by its title\"\nauthor: mock\n---\n\nThe front matter is hidden.",
+            &["mock"],
+        ),
+    ]
+}
@@ -0,0 +1,69 @@
+use web_time::{Duration, Instant};
+
+/// How long a toast stays up unless it is clicked away.
+const LIFETIME: Duration = Duration::from_secs(4);
+
+struct Notification {
+    text: String,
+    is_error: bool,
+    created: Instant,
+}
+
+/// Short-lived messages stacked in the bottom right corner, e.g. to confirm a write.
+#[derive(Default)]
+pub(crate) struct Notifications {
+    queue: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.push(text.into(), false);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(text.into(), true);
+    }
+
+    fn push(&mut self, text: String, is_error: bool) {
+        self.queue.push(Notification {
+            text,
+            is_error,
+            created: Instant::now(),
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.queue.retain(|n| n.created.elapsed() < LIFETIME);
+        let Some(oldest) = self.queue.first() else {
+            return;
+        };
+        // Wake up to remove the oldest one even if nothing else happens
+        ctx.request_repaint_after(LIFETIME.saturating_sub(oldest.created.elapsed()));
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-8.0, -32.0))
+            .order(egui::Order::Foreground)
+            .interactable(true)
+            .show(ctx, |ui| {
+                for (index, notification) in self.queue.iter().enumerate() {
+                    let color = if notification.is_error {
+                        ui.visuals().error_fg_color
+                    } else {
+                        ui.visuals().text_color()
+                    };
+                    let response = egui::Frame::popup(ui.style())
+                        .show(ui, |ui| ui.colored_label(color, &notification.text))
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss");
+                    if response.clicked() {
+                        dismissed = Some(index);
+                    }
+                }
+            });
+        if let Some(index) = dismissed {
+            self.queue.remove(index);
+        }
+    }
+}
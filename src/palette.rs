@@ -0,0 +1,122 @@
+/// Something the command palette can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Reload,
+    NewEntry,
+    Export,
+    Import,
+    ToggleTheme,
+    OpenSettings,
+}
+
+impl Action {
+    const ALL: [Self; 6] = [
+        Self::Reload,
+        Self::NewEntry,
+        Self::Export,
+        Self::Import,
+        Self::ToggleTheme,
+        Self::OpenSettings,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Reload => "Reload entries",
+            Self::NewEntry => "New entry",
+            Self::Export => "Export entries",
+            Self::Import => "Import entries",
+            Self::ToggleTheme => "Toggle theme",
+            Self::OpenSettings => "Open settings",
+        }
+    }
+}
+
+/// Whether the letters of `query` appear in `label` in order, ignoring case, e.g. "nwe"
+/// matches "New entry".
+fn fuzzy_match(label: &str, query: &str) -> bool {
+    let mut label = label.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|q| label.any(|c| c == q))
+}
+
+/// Ctrl+P overlay for running actions by name.
+#[derive(Default)]
+pub(crate) struct CommandPalette {
+    open: bool,
+    query: String,
+    /// Index into the matching actions, moved with the arrow keys.
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Returns the action to run if one was picked.
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> Option<Action> {
+        if !self.open {
+            return None;
+        }
+
+        let matches: Vec<Action> = Action::ALL
+            .into_iter()
+            .filter(|action| fuzzy_match(action.label(), &self.query))
+            .collect();
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut picked = enter.then(|| matches.get(self.selected).copied()).flatten();
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .show(ctx, |ui| {
+                let query = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command")
+                        .desired_width(300.0),
+                );
+                query.request_focus();
+                if query.changed() {
+                    self.selected = 0;
+                }
+
+                for (index, action) in matches.iter().enumerate() {
+                    if ui
+                        .selectable_label(index == self.selected, action.label())
+                        .clicked()
+                    {
+                        picked = Some(*action);
+                    }
+                }
+                if matches.is_empty() {
+                    ui.weak("No matching command");
+                }
+            });
+
+        if picked.is_some() || escape {
+            self.open = false;
+        }
+        picked
+    }
+}
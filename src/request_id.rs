@@ -0,0 +1,36 @@
+//! Ids sent along with requests, so a failure seen here can be found in the server logs.
+
+/// The header they are sent in.
+pub const HEADER: &str = "X-Request-Id";
+
+/// A random (version 4) UUID, like `0f8fad5b-d9cb-469f-a165-70867728950e`.
+pub fn generate() -> String {
+    // Version 4 in the 13th hex digit, variant 1 (`10`) in the top bits of the 17th
+    let high = (random_u64() & !0xf000) | 0x4000;
+    let low = (random_u64() & !(0x3 << 62)) | (0x2 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// `RandomState` is seeded by the OS, unlike on web, where its keys are fixed.
+#[cfg(not(target_arch = "wasm32"))]
+fn random_u64() -> u64 {
+    use std::hash::{BuildHasher as _, Hasher as _};
+    // Every `RandomState` continues from different keys, so nothing needs hashing
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn random_u64() -> u64 {
+    // `Math.random` gives at least 32 random bits
+    let half = || (js_sys::Math::random() * 4_294_967_296.0) as u64;
+    (half() << 32) | half()
+}
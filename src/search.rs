@@ -0,0 +1,255 @@
+//! An inverted index of the words in the entries, so searching doesn't have to go through
+//! the text of every entry on each keystroke.
+
+use crate::app::Entry;
+use crate::find;
+use std::collections::BTreeMap;
+use std::ops::{Bound, Range};
+
+/// Queries shorter than this are looked for as typed, anywhere in the text. A prefix that
+/// short matches most of the words anyway.
+const MIN_INDEXED_QUERY: usize = 3;
+
+/// Maps each lowercased word to the entries it appears in. Has to be rebuilt whenever the
+/// entries change, as it refers to them by index.
+#[derive(Default)]
+pub(crate) struct SearchIndex {
+    /// The indices are ascending and without duplicates.
+    words: BTreeMap<String, Vec<usize>>,
+    /// Which version of the entries it was built from, see `MyceliaApp::entries_version`.
+    version: Option<u64>,
+}
+
+/// The words of `text`, split at anything that isn't a letter or digit.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+/// Whether a word of `text` starts at byte `index`.
+fn starts_word(text: &str, index: usize) -> bool {
+    let before = text
+        .get(..index)
+        .and_then(|before| before.chars().next_back());
+    !before.is_some_and(char::is_alphanumeric)
+}
+
+/// How entries are matched against a search query.
+pub enum Matching<'a> {
+    /// Short queries, and ones without any letters or digits: anywhere in the text.
+    Substring(&'a str),
+    /// Entries with a word starting with each of these.
+    WordPrefixes(Vec<&'a str>),
+}
+
+impl<'a> Matching<'a> {
+    pub fn new(query: &'a str) -> Self {
+        let terms: Vec<&str> = words(query).collect();
+        if query.chars().count() < MIN_INDEXED_QUERY || terms.is_empty() {
+            Self::Substring(query)
+        } else {
+            Self::WordPrefixes(terms)
+        }
+    }
+
+    /// Byte ranges of what matched in `text`, sorted by start, to highlight them.
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Self::Substring(query) => find::find_all(text, query, false),
+            Self::WordPrefixes(terms) => {
+                let mut ranges: Vec<Range<usize>> = (terms.iter())
+                    .flat_map(|term| find::find_all(text, term, false))
+                    .filter(|range| starts_word(text, range.start))
+                    .collect();
+                ranges.sort_by_key(|range| range.start);
+                ranges
+            }
+        }
+    }
+
+    /// Whether `text` matches, both it and the query being lowercase.
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            Self::Substring(query) => text.contains(query),
+            Self::WordPrefixes(terms) => terms
+                .iter()
+                .all(|term| words(text).any(|word| word.starts_with(term))),
+        }
+    }
+}
+
+impl SearchIndex {
+    /// Indexes `entries`, which are at `version`.
+    pub fn rebuild(&mut self, entries: &[Entry], version: u64) {
+        self.words.clear();
+        for (index, entry) in entries.iter().enumerate() {
+            let text = entry.text.to_lowercase();
+            for word in words(&text) {
+                if let Some(indices) = self.words.get_mut(word) {
+                    if indices.last() != Some(&index) {
+                        indices.push(index);
+                    }
+                } else {
+                    self.words.insert(word.to_owned(), vec![index]);
+                }
+            }
+        }
+        self.version = Some(version);
+    }
+
+    /// Indices of the `entries` matching `query`, see `Matching`, ascending. All of them for
+    /// an empty query. The index is only used if it was built from this `version` of them.
+    pub fn search(&self, entries: &[Entry], version: u64, query: &str) -> Vec<usize> {
+        if query.trim().is_empty() {
+            return (0..entries.len()).collect();
+        }
+        let query = query.to_lowercase();
+        let matching = Matching::new(&query);
+        let terms = match &matching {
+            Matching::WordPrefixes(terms) if self.version == Some(version) => terms,
+            // Not rebuilt yet would be a bug, but one that shouldn't hide entries
+            _ => {
+                return (entries.iter().enumerate())
+                    .filter(|(_, e)| matching.matches(&e.text.to_lowercase()))
+                    .map(|(i, _)| i)
+                    .collect();
+            }
+        };
+
+        let mut matches: Option<Vec<usize>> = None;
+        for &term in terms {
+            let mut found: Vec<usize> = (self.words)
+                .range::<str, _>((Bound::Included(term), Bound::Unbounded))
+                .take_while(|(word, _)| word.starts_with(term))
+                .flat_map(|(_, indices)| indices.iter().copied())
+                .collect();
+            found.sort_unstable();
+            found.dedup();
+            matches = Some(match matches {
+                Some(mut matches) => {
+                    matches.retain(|i| found.binary_search(i).is_ok());
+                    matches
+                }
+                None => found,
+            });
+        }
+        matches.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &[&str] = &["apple", "banana", "cherry", "async", "fungus", "mycelium"];
+
+    /// Entry `i` has words `i % 6` and `i / 6 % 6`, and ends with its number.
+    fn entries(count: usize) -> Vec<Entry> {
+        (0..count)
+            .map(|i| Entry {
+                id: i.to_string(),
+                text: format!(
+                    "{} and {} #{i}",
+                    WORDS.get(i % WORDS.len()).unwrap_or(&""),
+                    WORDS.get(i / WORDS.len() % WORDS.len()).unwrap_or(&""),
+                ),
+                ..Entry::default()
+            })
+            .collect()
+    }
+
+    /// An index of `entries` as version 1 of them.
+    fn indexed(entries: &[Entry]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        index.rebuild(entries, 1);
+        index
+    }
+
+    /// What `search` should find, worked out the slow way.
+    fn containing_all(entries: &[Entry], terms: &[&str]) -> Vec<usize> {
+        (entries.iter().enumerate())
+            .filter(|(_, e)| terms.iter().all(|term| e.text.contains(term)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn words_are_and_terms() {
+        let entries = entries(5000);
+        let index = indexed(&entries);
+        let found = index.search(&entries, 1, "APPLE Cherry");
+        assert!(!found.is_empty());
+        assert_eq!(found, containing_all(&entries, &["apple", "cherry"]));
+        assert!(index.search(&entries, 1, "apple nothing").is_empty());
+    }
+
+    #[test]
+    fn terms_match_word_prefixes() {
+        let entries = entries(5000);
+        let index = indexed(&entries);
+        assert_eq!(
+            index.search(&entries, 1, "asy fun"),
+            containing_all(&entries, &["async", "fungus"]),
+        );
+        assert!(index.search(&entries, 1, "sync").is_empty());
+
+        let text = "Apple pineapple appetite";
+        assert_eq!(Matching::new("app").ranges(text), [0..3, 16..19]);
+    }
+
+    #[test]
+    fn short_queries_are_substrings() {
+        let entries = entries(5000);
+        let index = indexed(&entries);
+        // Spans a word and the space after it, which the index doesn't know of
+        assert_eq!(
+            index.search(&entries, 1, "e "),
+            containing_all(&entries, &["e "])
+        );
+        assert_eq!(
+            index.search(&entries, 1, "#1"),
+            containing_all(&entries, &["#1"])
+        );
+    }
+
+    #[test]
+    fn empty_query_finds_everything() {
+        let entries = entries(5000);
+        let index = indexed(&entries);
+        let all: Vec<usize> = (0..entries.len()).collect();
+        assert_eq!(index.search(&entries, 1, ""), all);
+        assert_eq!(index.search(&entries, 1, "  "), all);
+    }
+
+    #[test]
+    fn rebuild_follows_the_entries() {
+        let mut entries = entries(5000);
+        let mut index = indexed(&entries);
+        entries.truncate(3000);
+        if let Some(entry) = entries.first_mut() {
+            entry.text = "zucchini".to_owned();
+        }
+        index.rebuild(&entries, 2);
+        assert_eq!(index.search(&entries, 2, "zucchini"), vec![0]);
+        assert_eq!(
+            index.search(&entries, 2, "banana"),
+            containing_all(&entries, &["banana"]),
+        );
+        assert!(index.search(&entries, 2, "#4999").is_empty());
+    }
+
+    #[test]
+    fn outdated_index_isnt_used() {
+        let mut entries = entries(5000);
+        let index = indexed(&entries);
+        // As many entries as before, only the version tells they changed
+        if let Some(entry) = entries.first_mut() {
+            entry.text = "zucchini".to_owned();
+        }
+        assert_eq!(index.search(&entries, 2, "zucchini"), vec![0]);
+        assert_eq!(
+            index.search(&entries, 2, "apple"),
+            containing_all(&entries, &["apple"]),
+        );
+    }
+}
@@ -0,0 +1,647 @@
+use crate::markdown;
+use crate::request_id;
+use std::time::Duration;
+
+/// What the API key field holds until the user fills it in.
+const API_KEY_PLACEHOLDER: &str = "Insert api key";
+
+/// How the API key is put into the `Authorization` header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum AuthScheme {
+    #[default]
+    Bearer,
+    Token,
+    /// The key holds `user:password`, which is sent base64 encoded.
+    Basic,
+    /// The key is sent as it is, without a scheme.
+    Raw,
+}
+
+impl AuthScheme {
+    const ALL: [Self; 4] = [Self::Bearer, Self::Token, Self::Basic, Self::Raw];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Bearer => "Bearer",
+            Self::Token => "Token",
+            Self::Basic => "Basic (user:password)",
+            Self::Raw => "Raw key",
+        }
+    }
+}
+
+/// A named set of connection settings, e.g. for a dev and a production server.
+///
+/// Not `Debug`, so the key can't end up in a log by accident.
+#[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Profile {
+    name: String,
+    base_url: String,
+    api_key: String,
+    auth_scheme: AuthScheme,
+}
+
+/// What auto-refresh does while the app is out of sight: the browser tab in the background,
+/// or on native the window unfocused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub(crate) enum HiddenPolling {
+    #[default]
+    Pause,
+    /// Poll five times less often.
+    Slow,
+    Continue,
+}
+
+impl HiddenPolling {
+    const ALL: [Self; 3] = [Self::Pause, Self::Slow, Self::Continue];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pause => "pause",
+            Self::Slow => "slow down",
+            Self::Continue => "keep going",
+        }
+    }
+}
+
+/// Whether the extra header `key` is left out of requests: an Authorization header only
+/// replaces the one with the API key if that is allowed.
+fn is_ignored_header(key: &str, override_authorization: bool) -> bool {
+    key.eq_ignore_ascii_case("Authorization") && !override_authorization
+}
+
+/// Settings window holding the connection configuration.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
+pub(crate) struct SettingsComponent {
+    /// Whether the window is shown. The values below are edited in place, so closing it
+    /// keeps whatever was typed.
+    open: bool,
+
+    pub api_key: String,
+    pub auth_scheme: AuthScheme,
+    /// The key is masked unless this is set, e.g. so it doesn't leak when screen-sharing.
+    show_api_key: bool,
+
+    /// Where the messages API lives, e.g. `https://mycelia.nel.re`.
+    pub base_url: String,
+
+    profiles: Vec<Profile>,
+    /// Name of the profile the connection fields above belong to, edits go to it as well.
+    profile: Option<String>,
+    /// Name typed in to save the connection fields as a new profile.
+    #[serde(skip)]
+    profile_name: String,
+    /// Set when another profile was switched to, until the app picked that up.
+    #[serde(skip)]
+    profile_switched: bool,
+
+    /// How long to wait for the server before giving up on a fetch.
+    pub timeout_secs: u64,
+
+    /// How many times a failed fetch is retried before the error is shown, 0 disables retrying.
+    pub retries: u32,
+
+    /// Reload automatically this long after the last fetch completed, `None` disables it.
+    pub poll_interval: Option<Duration>,
+    pub hidden_polling: HiddenPolling,
+
+    /// Fetch the entries when the app starts, if there is an API key to do so.
+    pub auto_fetch_on_start: bool,
+
+    pub dark_mode: bool,
+
+    /// Zoom factor of the whole UI, the same one egui's Ctrl +/- shortcuts change.
+    pub ui_scale: f32,
+
+    /// Interpret HTML in entries instead of showing it as written. Only safe when the
+    /// entries come from a trusted source, see `markdown::viewer`.
+    pub render_html: bool,
+
+    /// Show `:tada:` style shortcodes as emoji when rendering entries.
+    pub emoji_shortcodes: bool,
+
+    /// Edit entries in a monospace font, e.g. to line up markdown tables. The preview
+    /// stays proportional.
+    pub editor_monospace: bool,
+
+    /// Longest text in characters the server accepts, if it has a limit. Longer entries
+    /// can't be saved from the editor.
+    pub max_entry_len: Option<usize>,
+
+    /// Fetch images entries link to. Turning it off keeps their servers from learning
+    /// when an entry is viewed.
+    pub remote_images: bool,
+
+    /// Sent with every request besides our own, e.g. for a gateway in front of the server.
+    pub extra_headers: Vec<(String, String)>,
+    /// Let `extra_headers` replace the bearer token, which they are ignored for otherwise.
+    pub override_authorization: bool,
+
+    /// Skip entries the server sends without an id or text, rather than listing them.
+    pub validate_entries: bool,
+
+    /// Show timings and sizes of requests in the status bar.
+    pub debug_info: bool,
+
+    /// Send every request with a generated `X-Request-Id`, logged next to the response
+    /// status, to find the request in the server logs.
+    pub request_ids: bool,
+
+    /// Fetching returns canned entries instead of asking the server, for UI work
+    /// without a server or credentials.
+    pub mock_mode: bool,
+
+    /// Set while asking whether to really clear the local data.
+    #[serde(skip)]
+    confirm_clear: bool,
+    /// Set when logging out was clicked, until the app picked that up.
+    #[serde(skip)]
+    log_out_clicked: bool,
+}
+
+impl SettingsComponent {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Whether another profile was switched to since the last call, so the entries shown
+    /// are from the wrong server.
+    pub fn take_profile_switched(&mut self) -> bool {
+        std::mem::take(&mut self.profile_switched)
+    }
+
+    /// Whether logging out was clicked since the last call.
+    pub fn take_log_out(&mut self) -> bool {
+        std::mem::take(&mut self.log_out_clicked)
+    }
+
+    /// Forgets the API key, and those of all profiles.
+    pub fn log_out(&mut self) {
+        let keys = std::iter::once(&mut self.api_key)
+            .chain(self.profiles.iter_mut().map(|profile| &mut profile.api_key));
+        for key in keys {
+            *key = API_KEY_PLACEHOLDER.to_owned();
+        }
+        self.show_api_key = false;
+    }
+
+    fn switch_profile(&mut self, profile: &Profile) {
+        self.profile = Some(profile.name.clone());
+        self.base_url.clone_from(&profile.base_url);
+        self.api_key.clone_from(&profile.api_key);
+        self.auth_scheme = profile.auth_scheme;
+        self.profile_switched = true;
+        log::info!("Switched to profile {:?}", profile.name);
+    }
+
+    /// Saves the connection fields under `name`, replacing a profile of that name.
+    fn save_profile(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        self.profiles.push(Profile {
+            name: name.to_owned(),
+            base_url: self.base_url.clone(),
+            api_key: self.api_key.clone(),
+            auth_scheme: self.auth_scheme,
+        });
+        self.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        self.profile = Some(name.to_owned());
+    }
+
+    /// Keeps the active profile up to date with edits of the connection fields.
+    fn update_profile(&mut self) {
+        let Some(name) = &self.profile else {
+            return;
+        };
+        if let Some(profile) = self.profiles.iter_mut().find(|p| p.name == *name) {
+            profile.base_url.clone_from(&self.base_url);
+            profile.api_key.clone_from(&self.api_key);
+            profile.auth_scheme = self.auth_scheme;
+        }
+    }
+
+    fn show_profiles(&mut self, ui: &mut egui::Ui) {
+        ui.label("Profile");
+        ui.horizontal(|ui| {
+            let mut switch = None;
+            egui::ComboBox::from_id_salt("profile")
+                .selected_text(self.profile.as_deref().unwrap_or("none"))
+                .show_ui(ui, |ui| {
+                    if self.profiles.is_empty() {
+                        ui.weak("No profiles saved yet");
+                    }
+                    for profile in &self.profiles {
+                        let active = self.profile.as_ref() == Some(&profile.name);
+                        if ui.selectable_label(active, &profile.name).clicked() && !active {
+                            switch = Some(profile.clone());
+                        }
+                    }
+                });
+            if let Some(profile) = switch {
+                self.switch_profile(&profile);
+            }
+            if let Some(name) = &self.profile
+                && ui
+                    .button("delete")
+                    .on_hover_text("Forget the profile, the fields keep its values")
+                    .clicked()
+            {
+                let name = name.clone();
+                self.profiles.retain(|p| p.name != name);
+                self.profile = None;
+            }
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.profile_name)
+                    .hint_text("name")
+                    .desired_width(80.0),
+            );
+            let name = self.profile_name.trim().to_owned();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("save as"))
+                .on_hover_text("Save the key, scheme and server below as a profile")
+                .clicked()
+            {
+                self.save_profile(&name);
+                self.profile_name.clear();
+            }
+        });
+        ui.end_row();
+    }
+
+    /// Whether an API key was entered, sending the placeholder is a guaranteed 401.
+    pub fn has_api_key(&self) -> bool {
+        let key = self.api_key.trim();
+        !key.is_empty() && key != API_KEY_PLACEHOLDER
+    }
+
+    pub fn messages_url(&self) -> String {
+        format!("{}/api/messages", self.base_url.trim_end_matches('/'))
+    }
+
+    pub fn message_url(&self, id: &str) -> String {
+        format!("{}/{id}", self.messages_url())
+    }
+
+    pub fn markdown(&self) -> markdown::Options {
+        markdown::Options {
+            render_html: self.render_html,
+            emoji: self.emoji_shortcodes,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn authorization(&self) -> String {
+        match self.auth_scheme {
+            AuthScheme::Bearer => format!("Bearer {}", self.api_key),
+            AuthScheme::Token => format!("Token {}", self.api_key),
+            AuthScheme::Basic => {
+                use base64::Engine as _;
+                let credentials = base64::engine::general_purpose::STANDARD.encode(&self.api_key);
+                format!("Basic {credentials}")
+            }
+            AuthScheme::Raw => self.api_key.clone(),
+        }
+    }
+
+    /// `headers` merged with the extra headers, which replace headers of the same name,
+    /// and a new request id if enabled.
+    pub fn headers(&self, headers: &[(&str, &str)]) -> ehttp::Headers {
+        let mut merged = ehttp::Headers::new(headers);
+        if self.request_ids {
+            merged.insert(request_id::HEADER, request_id::generate());
+        }
+        for (key, value) in &self.extra_headers {
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+            // The settings say so where the header is entered, see `show_extra_headers`
+            if is_ignored_header(key, self.override_authorization) {
+                continue;
+            }
+            merged.headers.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+            merged.insert(key, value);
+        }
+        merged
+    }
+
+    /// `auth_failed` highlights the API key, as the server just rejected it.
+    ///
+    /// Returns `true` once the user confirmed clearing all local data.
+    pub(crate) fn show(&mut self, ctx: &egui::Context, auth_failed: bool) -> bool {
+        let mut open = self.open;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings")
+                    .num_columns(2)
+                    .show(ui, |ui| self.show_fields(ui, auth_failed));
+                self.update_profile();
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Log out")
+                        .on_hover_text("Forget the API keys and the entries fetched with them")
+                        .clicked()
+                    {
+                        self.log_out_clicked = true;
+                    }
+                    if ui
+                        .button("Clear local data")
+                        .on_hover_text("Forget the settings, cached entries and drafts")
+                        .clicked()
+                    {
+                        self.confirm_clear = true;
+                    }
+                });
+            });
+        self.open = open;
+
+        self.show_clear_confirmation(ctx)
+    }
+
+    fn show_clear_confirmation(&mut self, ctx: &egui::Context) -> bool {
+        if !self.confirm_clear {
+            return false;
+        }
+
+        let mut clear = false;
+        egui::Window::new("Clear local data?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("This resets the app as if it was launched for the first time.");
+                ui.label("The entries on the server are not affected.");
+                ui.horizontal(|ui| {
+                    if ui.button("Clear").clicked() {
+                        self.confirm_clear = false;
+                        clear = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_clear = false;
+                    }
+                });
+            });
+        clear
+    }
+
+    fn show_fields(&mut self, ui: &mut egui::Ui, auth_failed: bool) {
+        self.show_profiles(ui);
+
+        ui.label("API key");
+        ui.horizontal(|ui| {
+            ui.scope(|ui| {
+                if auth_failed {
+                    let stroke = egui::Stroke::new(1.0, ui.visuals().error_fg_color);
+                    ui.visuals_mut().widgets.inactive.bg_stroke = stroke;
+                    ui.visuals_mut().widgets.hovered.bg_stroke = stroke;
+                }
+                ui.add(egui::TextEdit::singleline(&mut self.api_key).password(!self.show_api_key));
+            });
+            let toggle = if self.show_api_key { "hide" } else { "show" };
+            if ui.button(toggle).clicked() {
+                self.show_api_key = !self.show_api_key;
+            }
+        });
+        ui.end_row();
+
+        ui.label("Scheme");
+        egui::ComboBox::from_id_salt("auth_scheme")
+            .selected_text(self.auth_scheme.label())
+            .show_ui(ui, |ui| {
+                for scheme in AuthScheme::ALL {
+                    ui.selectable_value(&mut self.auth_scheme, scheme, scheme.label());
+                }
+            });
+        ui.end_row();
+
+        ui.label("Server");
+        ui.text_edit_singleline(&mut self.base_url);
+        ui.end_row();
+
+        ui.label("Timeout");
+        ui.add(
+            egui::DragValue::new(&mut self.timeout_secs)
+                .range(1..=600)
+                .suffix(" s"),
+        );
+        ui.end_row();
+
+        ui.label("Retries");
+        ui.add(egui::DragValue::new(&mut self.retries).range(0..=10));
+        ui.end_row();
+
+        ui.label("Auto-refresh");
+        let label = |interval: Option<Duration>| match interval {
+            Some(interval) => format!("every {}s", interval.as_secs()),
+            None => "off".to_owned(),
+        };
+        egui::ComboBox::from_id_salt("poll_interval")
+            .selected_text(label(self.poll_interval))
+            .show_ui(ui, |ui| {
+                for interval in [None, Some(5), Some(15), Some(60)] {
+                    let interval = interval.map(Duration::from_secs);
+                    ui.selectable_value(&mut self.poll_interval, interval, label(interval));
+                }
+            });
+        ui.end_row();
+
+        ui.label("When hidden");
+        ui.add_enabled_ui(self.poll_interval.is_some(), |ui| {
+            egui::ComboBox::from_id_salt("hidden_polling")
+                .selected_text(self.hidden_polling.label())
+                .show_ui(ui, |ui| {
+                    for polling in HiddenPolling::ALL {
+                        ui.selectable_value(&mut self.hidden_polling, polling, polling.label());
+                    }
+                })
+                .response
+                .on_hover_text("Auto-refresh while the tab is in the background");
+        });
+        ui.end_row();
+
+        ui.label("Startup");
+        ui.checkbox(&mut self.auto_fetch_on_start, "fetch entries")
+            .on_hover_text("Otherwise the entries from last time are shown until you reload");
+        ui.end_row();
+
+        ui.label("Theme");
+        let theme = if self.dark_mode {
+            "🌙 dark"
+        } else {
+            "☀ light"
+        };
+        if ui.button(theme).clicked() {
+            self.dark_mode = !self.dark_mode;
+        }
+        ui.end_row();
+
+        ui.label("Headers");
+        self.show_extra_headers(ui);
+        ui.end_row();
+
+        ui.label("UI scale");
+        let slider = ui.add(
+            egui::Slider::new(&mut self.ui_scale, 0.8..=2.0)
+                .step_by(0.1)
+                .clamping(egui::SliderClamping::Edits),
+        );
+        // Rescaling while dragging would move the slider away under the pointer
+        if slider.drag_stopped() || (slider.changed() && !slider.dragged()) {
+            ui.ctx().set_zoom_factor(self.ui_scale);
+        }
+        ui.end_row();
+
+        self.show_entry_fields(ui);
+        self.show_debug_fields(ui);
+    }
+
+    /// How entries are edited, rendered and fetched.
+    fn show_entry_fields(&mut self, ui: &mut egui::Ui) {
+        ui.label("Editor");
+        ui.checkbox(&mut self.editor_monospace, "monospace font");
+        ui.end_row();
+
+        ui.label("Max length");
+        ui.horizontal(|ui| {
+            let mut limited = self.max_entry_len.is_some();
+            if ui.checkbox(&mut limited, "").changed() {
+                self.max_entry_len = limited.then_some(10_000);
+            }
+            if let Some(max) = &mut self.max_entry_len {
+                ui.add(
+                    egui::DragValue::new(max)
+                        .range(1..=usize::MAX)
+                        .suffix(" characters"),
+                );
+            } else {
+                ui.weak("no limit");
+            }
+        })
+        .response
+        .on_hover_text("What the server accepts, longer entries can't be saved");
+        ui.end_row();
+
+        ui.label("Images");
+        ui.checkbox(&mut self.remote_images, "load remote images")
+            .on_hover_text("The servers hosting them can tell when you view an entry");
+        ui.end_row();
+
+        ui.label("HTML");
+        ui.checkbox(&mut self.render_html, "render")
+            .on_hover_text("Only enable this if you trust whoever wrote the entries");
+        ui.end_row();
+
+        ui.label("Emoji");
+        ui.checkbox(&mut self.emoji_shortcodes, "show :tada: as 🎉")
+            .on_hover_text("Only when rendering, the text keeps the shortcodes");
+        ui.end_row();
+
+        ui.label("Entries");
+        ui.checkbox(&mut self.validate_entries, "skip ones without id or text")
+            .on_hover_text("Applies from the next reload");
+        ui.end_row();
+    }
+
+    fn show_debug_fields(&mut self, ui: &mut egui::Ui) {
+        ui.label("Debug");
+        ui.vertical(|ui| {
+            ui.checkbox(&mut self.debug_info, "show request timings");
+            ui.checkbox(&mut self.request_ids, "send request ids")
+                .on_hover_text(format!(
+                    "Sent as {}, and logged with the response. \
+                    On web the server has to allow the header",
+                    request_id::HEADER
+                ));
+            ui.checkbox(&mut self.mock_mode, "mock mode")
+                .on_hover_text("Show sample entries instead of fetching them");
+        });
+        ui.end_row();
+    }
+
+    fn show_extra_headers(&mut self, ui: &mut egui::Ui) {
+        let override_authorization = self.override_authorization;
+        ui.vertical(|ui| {
+            let mut remove = None;
+            for (index, (key, value)) in self.extra_headers.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(key)
+                            .hint_text("Name")
+                            .desired_width(120.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(value)
+                            .hint_text("Value")
+                            .desired_width(160.0),
+                    );
+                    if ui.button("✖").on_hover_text("Remove").clicked() {
+                        remove = Some(index);
+                    }
+                    if is_ignored_header(key.trim(), override_authorization) {
+                        ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                            .on_hover_text("Not sent, replacing it isn't allowed below");
+                    }
+                });
+            }
+            if let Some(index) = remove {
+                self.extra_headers.remove(index);
+            }
+            if ui.button("add header").clicked() {
+                self.extra_headers.push(Default::default());
+            }
+            ui.checkbox(
+                &mut self.override_authorization,
+                "may replace the Authorization header",
+            );
+        });
+    }
+}
+
+impl Default for SettingsComponent {
+    fn default() -> Self {
+        Self {
+            open: false,
+            api_key: API_KEY_PLACEHOLDER.to_owned(),
+            auth_scheme: AuthScheme::Bearer,
+            show_api_key: false,
+            base_url: "https://mycelia.nel.re".to_owned(),
+            profiles: vec![],
+            profile: None,
+            profile_name: String::new(),
+            profile_switched: false,
+            timeout_secs: 30,
+            retries: 3,
+            poll_interval: None,
+            hidden_polling: HiddenPolling::default(),
+            auto_fetch_on_start: true,
+            dark_mode: true,
+            ui_scale: 1.0,
+            render_html: false,
+            emoji_shortcodes: true,
+            editor_monospace: false,
+            max_entry_len: None,
+            remote_images: true,
+            extra_headers: vec![],
+            override_authorization: false,
+            validate_entries: false,
+            debug_info: false,
+            // Cross-origin requests with it fail unless the server allows the header
+            request_ids: cfg!(not(target_arch = "wasm32")),
+            mock_mode: false,
+            confirm_clear: false,
+            log_out_clicked: false,
+        }
+    }
+}